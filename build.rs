@@ -1,29 +1,93 @@
-use std::process::Command;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[path = "src/tz/zic.rs"]
+mod zic;
+
+const REGIONS: [&str; 6] = [
+    "africa",
+    "asia",
+    "antarctica",
+    "northamerica",
+    "southamerica",
+    "europe",
+];
 
 fn main() {
-    for version in ["2018c", "2018g", "2021b", "2023d"] {
-        let tzdata_dir = format!("./external/{}", version);
-        let output_dir = format!("../../input/zoneinfo/{}", version);
-
-        let status = Command::new("zic")
-            .args([
-                "-d",
-                &output_dir,
-                "africa",
-                "asia",
-                "antarctica",
-                "northamerica",
-                "southamerica",
-                "europe",
-            ])
-            .current_dir(tzdata_dir)
-            .status()
-            .expect("Failed to execute zic");
-
-        if !status.success() {
-            panic!("zic failed with status: {}", status);
+    // Compiled zones go to `$ZONEINFO_DIR/<version>` when set, which also
+    // allows pointing the build at a directory of prebuilt TZif files, and to
+    // `$OUT_DIR/zoneinfo/<version>` otherwise.
+    let output_root = match env::var("ZONEINFO_DIR") {
+        Ok(dir) => PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join(dir),
+        Err(_) => PathBuf::from(env::var("OUT_DIR").unwrap()).join("zoneinfo"),
+    };
+
+    // Every directory under `external/` is a tzdata release, so a new one can
+    // be added by dropping its sources in place. Prebuilt versions in the
+    // output directory are picked up as well.
+    let mut versions = subdirectories(Path::new("./external"));
+    versions.extend(subdirectories(&output_root));
+    versions.sort();
+    versions.dedup();
+
+    let mut available = Vec::new();
+
+    for version in versions {
+        let tzdata_dir = Path::new("./external").join(&version);
+        let output_dir = output_root.join(&version);
+
+        let sources: Vec<PathBuf> = REGIONS
+            .iter()
+            .map(|region| tzdata_dir.join(region))
+            .collect();
+
+        // Sources are compiled afresh whenever the build script runs, which
+        // is whenever something under `external/` changes, so the zones never
+        // lag behind them. Versions without sources use prebuilt zones.
+        if !sources.iter().all(|source| source.is_file()) {
+            match has_entries(&output_dir) {
+                true => available.push(version),
+                false => println!(
+                    "cargo:warning=Skipping tzdata {}: no sources in {} and no prebuilt zones in {}",
+                    version,
+                    tzdata_dir.display(),
+                    output_dir.display()
+                ),
+            }
+            continue;
+        }
+
+        if output_dir.exists() {
+            fs::remove_dir_all(&output_dir).unwrap();
+        }
+
+        if let Err(e) = zic::compile(&sources, &output_dir) {
+            panic!("Could not compile tzdata {}: {}", version, e);
         }
+
+        available.push(version);
     }
 
+    println!("cargo:rustc-env=ZONEINFO_DIR={}", output_root.display());
+    println!("cargo:rustc-env=TZDATA_VERSIONS={}", available.join(","));
     println!("cargo:rerun-if-changed=external/");
+    println!("cargo:rerun-if-env-changed=ZONEINFO_DIR");
+}
+
+fn subdirectories(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn has_entries(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
 }
@@ -0,0 +1,516 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    ops::RangeInclusive,
+};
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+
+use super::interval::IntervalSet;
+
+/// A VEVENT reduced to the properties needed for holidays and windows.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Event {
+    start: Option<Moment>,
+    end: Option<Moment>,
+    rule: Option<String>,
+    exceptions: Vec<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Moment {
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+}
+
+impl Moment {
+    fn date(&self) -> NaiveDate {
+        match self {
+            Moment::Date(date) => *date,
+            Moment::DateTime(datetime) => datetime.date_naive(),
+        }
+    }
+}
+
+/// Reads every holiday date in `years` from the VEVENTs of an iCalendar file.
+/// Multi-day events contribute each of their days, and `FREQ=YEARLY` rules are
+/// expanded with their `INTERVAL`, `COUNT`, `UNTIL`, `BYMONTH`, `BYMONTHDAY`
+/// and `BYDAY` parts.
+pub fn parse_holidays(ics: &str, years: RangeInclusive<i32>) -> Result<Vec<NaiveDate>> {
+    let mut holidays = Vec::new();
+
+    for event in parse_events(ics)? {
+        let start = event
+            .start
+            .ok_or_else(|| invalid("VEVENT without DTSTART"))?
+            .date();
+        let length = match event.end {
+            Some(end) => (end.date() - start).num_days().max(1),
+            None => 1,
+        };
+
+        let occurrences = match &event.rule {
+            Some(rule) => expand_yearly(start, rule, &years)?,
+            None => vec![start],
+        };
+
+        for occurrence in occurrences {
+            if event.exceptions.contains(&occurrence) {
+                continue;
+            }
+
+            holidays.extend(
+                occurrence
+                    .iter_days()
+                    .take(length as usize)
+                    .filter(|day| years.contains(&day.year())),
+            );
+        }
+    }
+
+    holidays.sort();
+    holidays.dedup();
+
+    Ok(holidays)
+}
+
+/// Writes each interval as a VEVENT with UTC start and end times.
+pub fn export_intervals(intervals: &IntervalSet, summary: &str) -> String {
+    let stamp = format_datetime(&Utc::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//i18n-puzzles//business calendar//EN".to_string(),
+    ];
+
+    let uid_suffix = summary
+        .to_lowercase()
+        .replace(|c: char| !c.is_alphanumeric(), "-");
+
+    for interval in intervals.iter() {
+        let start = format_datetime(&interval.start);
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}-{}@i18n-puzzles", start, uid_suffix),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", start),
+            format!("DTEND:{}", format_datetime(&interval.end)),
+            format!("SUMMARY:{}", escape_text(summary)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+fn parse_events(ics: &str) -> Result<Vec<Event>> {
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+
+    for line in unfold_lines(ics) {
+        let (name, params, value) = split_property(&line)?;
+
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => current = Some(Event::default()),
+            ("END", "VEVENT") => events.extend(current.take()),
+            (_, value) => {
+                let Some(event) = current.as_mut() else {
+                    continue;
+                };
+
+                match name.as_str() {
+                    "DTSTART" => event.start = Some(parse_moment(&params, value)?),
+                    "DTEND" => event.end = Some(parse_moment(&params, value)?),
+                    "RRULE" => event.rule = Some(value.to_string()),
+                    "EXDATE" => {
+                        for exception in value.split(',') {
+                            event
+                                .exceptions
+                                .push(parse_moment(&params, exception)?.date());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Joins continuation lines, which start with a space or tab (RFC 5545 §3.1).
+fn unfold_lines(ics: &str) -> Vec<String> {
+    ics.lines().fold(Vec::new(), |mut lines, line| {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if !line.trim().is_empty() => lines.push(line.trim_end_matches('\r').to_string()),
+            _ => {}
+        }
+        lines
+    })
+}
+
+fn split_property(line: &str) -> Result<(String, Vec<String>, &str)> {
+    let (head, value) = line
+        .split_once(':')
+        .ok_or_else(|| invalid(&format!("Malformed content line: {}", line)))?;
+
+    let mut parts = head.split(';');
+    let name = parts.next().unwrap_or_default().to_ascii_uppercase();
+
+    Ok((name, parts.map(|p| p.to_ascii_uppercase()).collect(), value))
+}
+
+fn parse_moment(params: &[String], value: &str) -> Result<Moment> {
+    let value = value.trim();
+
+    if params.iter().any(|p| p == "VALUE=DATE") || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(Moment::Date)
+            .map_err(|e| invalid(&e.to_string()));
+    }
+
+    // Floating and TZID-qualified times are read as UTC, which is enough to
+    // recover the date of an all-day holiday.
+    NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+        .map(|datetime| Moment::DateTime(datetime.and_utc()))
+        .map_err(|e| invalid(&e.to_string()))
+}
+
+fn expand_yearly(
+    start: NaiveDate,
+    rule: &str,
+    years: &RangeInclusive<i32>,
+) -> Result<Vec<NaiveDate>> {
+    let parts: Vec<(String, &str)> = rule
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.to_ascii_uppercase(), value))
+        .collect();
+    let get = |key: &str| parts.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+
+    if get("FREQ") != Some("YEARLY") {
+        return Err(invalid(&format!(
+            "Only yearly rules are supported: {}",
+            rule
+        )));
+    }
+
+    let interval = match get("INTERVAL").map_or(Ok(1), str::parse) {
+        Ok(interval @ 1..) => interval,
+        _ => return Err(invalid(&format!("INTERVAL must be at least 1: {}", rule))),
+    };
+    let count: Option<usize> = get("COUNT")
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| invalid(rule))?;
+    let until = get("UNTIL")
+        .map(|until| parse_moment(&[], until).map(|m| m.date()))
+        .transpose()?;
+    let by_month: Option<u32> = get("BYMONTH")
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| invalid(rule))?;
+    let month = by_month.unwrap_or(start.month());
+    let day = get("BYMONTHDAY")
+        .map(str::parse::<u32>)
+        .transpose()
+        .map_err(|_| invalid(rule))?;
+    let weekday = get("BYDAY").map(parse_by_day).transpose()?;
+
+    let last_year = match until {
+        Some(until) => until.year().min(*years.end()),
+        None => *years.end(),
+    };
+
+    let mut occurrences = Vec::new();
+    let mut year = start.year();
+
+    while year <= last_year && count.is_none_or(|count| occurrences.len() < count) {
+        let occurrence = match (weekday, day) {
+            (Some((nth, weekday)), _) if by_month.is_none() => {
+                nth_weekday_of_year(year, weekday, nth)
+            }
+            (Some((nth, weekday)), _) => nth_weekday_of_month(year, month, weekday, nth),
+            (None, Some(day)) => NaiveDate::from_ymd_opt(year, month, day),
+            (None, None) => NaiveDate::from_ymd_opt(year, month, start.day()),
+        };
+
+        if let Some(date) = occurrence.filter(|date| *date >= start) {
+            if until.is_some_and(|until| date > until) {
+                break;
+            }
+            occurrences.push(date);
+        }
+
+        year += interval;
+    }
+
+    Ok(occurrences)
+}
+
+/// Parses a single `BYDAY` entry such as `4TH` or `-1MO`, whose ordinal must
+/// lie within ±53.
+fn parse_by_day(value: &str) -> Result<(i32, Weekday)> {
+    let split = value
+        .char_indices()
+        .rev()
+        .nth(1)
+        .map_or(0, |(index, _)| index);
+    let (nth, day) = value.split_at(split);
+
+    let weekday = match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(invalid(&format!("Invalid BYDAY: {}", value))),
+    };
+
+    // Occurrences are counted within the year or month, so there are at
+    // most 53 of them either way, and there is no 0th.
+    let nth = match nth.trim_start_matches('+') {
+        "" => 1,
+        nth => match nth.parse() {
+            Ok(nth @ (-53..=-1 | 1..=53)) => nth,
+            _ => return Err(invalid(&format!("Invalid BYDAY: {}", value))),
+        },
+    };
+
+    Ok((nth, weekday))
+}
+
+/// The `nth` `weekday` of the month, counting from its end if negative.
+fn nth_weekday_of_month(year: i32, month: u32, weekday: Weekday, nth: i32) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+
+    nth_weekday(first, last, weekday, nth)
+}
+
+/// The `nth` `weekday` of the year, as `BYDAY` counts them in yearly rules
+/// without `BYMONTH`.
+fn nth_weekday_of_year(year: i32, weekday: Weekday, nth: i32) -> Option<NaiveDate> {
+    nth_weekday(
+        NaiveDate::from_ymd_opt(year, 1, 1)?,
+        NaiveDate::from_ymd_opt(year, 12, 31)?,
+        weekday,
+        nth,
+    )
+}
+
+/// The `nth` `weekday` from `first` to `last`, counting back from `last` if
+/// `nth` is negative, or `None` if there are not that many.
+fn nth_weekday(first: NaiveDate, last: NaiveDate, weekday: Weekday, nth: i32) -> Option<NaiveDate> {
+    let date = if nth > 0 {
+        let offset =
+            (weekday.num_days_from_monday() + 7 - first.weekday().num_days_from_monday()) % 7;
+        first + Duration::days(offset as i64) + Duration::weeks((nth - 1) as i64)
+    } else {
+        let offset =
+            (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+        last - Duration::days(offset as i64) - Duration::weeks((-nth - 1) as i64)
+    };
+
+    (first..=last).contains(&date).then_some(date)
+}
+
+fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits a content line into CRLF-terminated chunks of at most 75 octets.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::super::interval::Interval;
+    use super::*;
+
+    fn calendar(events: &[&str]) -> String {
+        let events: Vec<String> = events
+            .iter()
+            .map(|event| format!("BEGIN:VEVENT\r\n{}\r\nEND:VEVENT\r\n", event))
+            .collect();
+
+        format!("BEGIN:VCALENDAR\r\n{}END:VCALENDAR\r\n", events.concat())
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn single_and_multi_day_events() {
+        let ics = calendar(&[
+            "DTSTART;VALUE=DATE:20220101",
+            "DTSTART;VALUE=DATE:20221226\r\nDTEND;VALUE=DATE:20221228",
+        ]);
+
+        assert_eq!(
+            parse_holidays(&ics, 2022..=2022).unwrap(),
+            [date(2022, 1, 1), date(2022, 12, 26), date(2022, 12, 27)]
+        );
+    }
+
+    #[test]
+    fn yearly_rules_by_weekday() {
+        let ics = calendar(&[
+            "DTSTART;VALUE=DATE:20200101\r\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+            "DTSTART;VALUE=DATE:20200101\r\nRRULE:FREQ=YEARLY;BYMONTH=5;BYDAY=-1MO",
+        ]);
+
+        assert_eq!(
+            parse_holidays(&ics, 2022..=2023).unwrap(),
+            [
+                date(2022, 5, 30),
+                date(2022, 11, 24),
+                date(2023, 5, 29),
+                date(2023, 11, 23)
+            ]
+        );
+    }
+
+    #[test]
+    fn yearly_rules_with_limits_and_exceptions() {
+        let ics = calendar(&[
+            "DTSTART;VALUE=DATE:20200704\r\nRRULE:FREQ=YEARLY;UNTIL=20220704\r\nEXDATE;VALUE=DATE:20210704",
+            "DTSTART;VALUE=DATE:20201225\r\nRRULE:FREQ=YEARLY;INTERVAL=2;COUNT=2",
+        ]);
+
+        assert_eq!(
+            parse_holidays(&ics, 2019..=2025).unwrap(),
+            [
+                date(2020, 7, 4),
+                date(2020, 12, 25),
+                date(2022, 7, 4),
+                date(2022, 12, 25)
+            ]
+        );
+    }
+
+    #[test]
+    fn by_day_ordinals() {
+        assert_eq!(parse_by_day("MO").unwrap(), (1, Weekday::Mon));
+        assert_eq!(parse_by_day("+2TU").unwrap(), (2, Weekday::Tue));
+        assert_eq!(parse_by_day("-53SU").unwrap(), (-53, Weekday::Sun));
+        assert!(parse_by_day("0MO").is_err());
+        assert!(parse_by_day("54MO").is_err());
+        assert!(parse_by_day("-300MO").is_err());
+        assert!(parse_by_day("1XX").is_err());
+    }
+
+    #[test]
+    fn yearly_rules_by_weekday_of_the_year() {
+        let ics = calendar(&[
+            "DTSTART;VALUE=DATE:20200101\r\nRRULE:FREQ=YEARLY;BYDAY=20MO",
+            "DTSTART;VALUE=DATE:20200101\r\nRRULE:FREQ=YEARLY;BYDAY=-1FR",
+            "DTSTART;VALUE=DATE:20200101\r\nRRULE:FREQ=YEARLY;BYDAY=53SA",
+        ]);
+
+        // 2022 starts and ends on a Saturday, giving it 53; 2023 has only 52.
+        assert_eq!(
+            parse_holidays(&ics, 2022..=2023).unwrap(),
+            [
+                date(2022, 5, 16),
+                date(2022, 12, 30),
+                date(2022, 12, 31),
+                date(2023, 5, 15),
+                date(2023, 12, 29)
+            ]
+        );
+        assert_eq!(
+            nth_weekday_of_year(2022, Weekday::Sat, 53),
+            Some(date(2022, 12, 31))
+        );
+        assert_eq!(nth_weekday_of_year(2023, Weekday::Sat, 53), None);
+    }
+
+    #[test]
+    fn intervals_below_one_are_errors() {
+        for interval in ["0", "-1", "x"] {
+            let ics = calendar(&[&format!(
+                "DTSTART;VALUE=DATE:20200101\r\nRRULE:FREQ=YEARLY;INTERVAL={}",
+                interval
+            )]);
+            assert!(parse_holidays(&ics, 2022..=2022).is_err(), "{}", interval);
+        }
+    }
+
+    #[test]
+    fn non_ascii_by_day_is_an_error() {
+        assert!(parse_by_day("1MÖ").is_err());
+        assert!(parse_by_day("Ö").is_err());
+        assert!(parse_by_day("é1MO").is_err());
+    }
+
+    #[test]
+    fn unsupported_rules_are_errors() {
+        let ics = calendar(&["DTSTART;VALUE=DATE:20220101\r\nRRULE:FREQ=MONTHLY"]);
+        assert!(parse_holidays(&ics, 2022..=2022).is_err());
+
+        let ics = calendar(&["SUMMARY:No start"]);
+        assert!(parse_holidays(&ics, 2022..=2022).is_err());
+    }
+
+    #[test]
+    fn exported_intervals() {
+        let start = Utc.with_ymd_and_hms(2022, 1, 3, 7, 30, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2022, 1, 3, 16, 0, 0).unwrap();
+        let intervals = IntervalSet::from_intervals([Interval::new(start, end)]);
+
+        let ics = export_intervals(&intervals, "Staffed, mostly");
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20220103T073000Z\r\n"));
+        assert!(ics.contains("\r\nDTEND:20220103T160000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Staffed\\, mostly\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let line = "x".repeat(100);
+        let folded = fold_line(&line);
+
+        assert_eq!(folded, format!("{}\r\n {}\r\n", &line[..75], &line[75..]));
+        assert_eq!(unfold_lines(&folded), [line]);
+    }
+}
@@ -0,0 +1,247 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// A half-open span of UTC time, `[start, end)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Interval {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { start, end }
+    }
+
+    /// From the start of `first` to the start of the year after `last`, in UTC.
+    pub fn years(first: i32, last: i32) -> Self {
+        Self {
+            start: Utc.with_ymd_and_hms(first, 1, 1, 0, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(last + 1, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn duration(&self) -> Duration {
+        if self.is_empty() {
+            Duration::zero()
+        } else {
+            self.end - self.start
+        }
+    }
+
+    pub fn contains(&self, instant: &DateTime<Utc>) -> bool {
+        self.start <= *instant && *instant < self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let interval = Interval::new(self.start.max(other.start), self.end.min(other.end));
+        (!interval.is_empty()).then_some(interval)
+    }
+}
+
+/// A set of UTC instants stored as sorted, disjoint, non-adjacent intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_intervals(intervals: impl IntoIterator<Item = Interval>) -> Self {
+        let mut intervals: Vec<Interval> =
+            intervals.into_iter().filter(|i| !i.is_empty()).collect();
+        intervals.sort();
+
+        let merged = intervals
+            .into_iter()
+            .fold(Vec::<Interval>::new(), |mut merged, interval| {
+                match merged.last_mut() {
+                    Some(last) if interval.start <= last.end => {
+                        last.end = last.end.max(interval.end)
+                    }
+                    _ => merged.push(interval),
+                }
+                merged
+            });
+
+        Self { intervals: merged }
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, instant: &DateTime<Utc>) -> bool {
+        let index = self.intervals.partition_point(|i| i.end <= *instant);
+        self.intervals
+            .get(index)
+            .is_some_and(|interval| interval.contains(instant))
+    }
+
+    pub fn total(&self) -> Duration {
+        self.intervals
+            .iter()
+            .fold(Duration::zero(), |total, interval| {
+                total + interval.duration()
+            })
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        IntervalSet::from_intervals(self.intervals.iter().chain(other.intervals.iter()).copied())
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let (mut i, mut j) = (0, 0);
+        let mut intervals = Vec::new();
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+
+            if let Some(overlap) = a.intersection(&b) {
+                intervals.push(overlap);
+            }
+
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet::from_intervals(intervals)
+    }
+
+    /// The parts of `self` that are not covered by `other`.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let mut j = 0;
+
+        for interval in &self.intervals {
+            let mut start = interval.start;
+
+            while j < other.intervals.len() && other.intervals[j].end <= start {
+                j += 1;
+            }
+
+            let mut k = j;
+            while k < other.intervals.len() && other.intervals[k].start < interval.end {
+                let cut = other.intervals[k];
+                if cut.start > start {
+                    intervals.push(Interval::new(start, cut.start));
+                }
+                start = start.max(cut.end);
+                k += 1;
+            }
+
+            if start < interval.end {
+                intervals.push(Interval::new(start, interval.end));
+            }
+        }
+
+        IntervalSet::from_intervals(intervals)
+    }
+
+    /// The parts of `window` that are not covered by `self`.
+    pub fn gaps(&self, window: Interval) -> IntervalSet {
+        IntervalSet::from_intervals([window]).difference(self)
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> Self {
+        IntervalSet::from_intervals(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The interval between two hours of 1 January 2022.
+    fn span(start: i64, end: i64) -> Interval {
+        let midnight = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        Interval::new(
+            midnight + Duration::hours(start),
+            midnight + Duration::hours(end),
+        )
+    }
+
+    fn set(spans: &[(i64, i64)]) -> IntervalSet {
+        spans.iter().map(|&(start, end)| span(start, end)).collect()
+    }
+
+    #[test]
+    fn overlapping_and_adjacent_intervals_merge() {
+        assert_eq!(
+            set(&[(3, 5), (1, 4), (5, 6), (8, 9)]),
+            set(&[(1, 6), (8, 9)])
+        );
+        assert_eq!(set(&[(1, 4), (2, 3)]).intervals(), &[span(1, 4)]);
+        assert_eq!(set(&[(2, 2), (4, 3)]), IntervalSet::new());
+    }
+
+    #[test]
+    fn union() {
+        let a = set(&[(0, 2), (6, 8)]);
+        let b = set(&[(2, 3), (7, 10), (12, 13)]);
+
+        assert_eq!(a.union(&b), set(&[(0, 3), (6, 10), (12, 13)]));
+        assert_eq!(a.union(&IntervalSet::new()), a);
+        assert_eq!(a.union(&b).total(), Duration::hours(8));
+    }
+
+    #[test]
+    fn intersection() {
+        let a = set(&[(0, 4), (6, 10)]);
+        let b = set(&[(2, 7), (9, 12)]);
+
+        assert_eq!(a.intersection(&b), set(&[(2, 4), (6, 7), (9, 10)]));
+        assert!(a.intersection(&IntervalSet::new()).is_empty());
+    }
+
+    #[test]
+    fn intervals_touching_at_a_point_do_not_overlap() {
+        let a = set(&[(0, 2)]);
+        let b = set(&[(2, 4)]);
+
+        assert!(a.intersection(&b).is_empty());
+        assert_eq!(a.difference(&b), a);
+        assert_eq!(a.union(&b), set(&[(0, 4)]));
+        assert!(a.contains(&span(1, 2).start));
+        assert!(!a.contains(&span(2, 3).start));
+    }
+
+    #[test]
+    fn difference_straddling_several_intervals() {
+        let a = set(&[(0, 3), (4, 6), (7, 10)]);
+        let b = set(&[(2, 8), (9, 11)]);
+
+        assert_eq!(a.difference(&b), set(&[(0, 2), (8, 9)]));
+        assert_eq!(b.difference(&a), set(&[(3, 4), (6, 7), (10, 11)]));
+        assert_eq!(a.difference(&IntervalSet::new()), a);
+        assert!(IntervalSet::new().difference(&a).is_empty());
+    }
+
+    #[test]
+    fn gaps_in_a_window() {
+        let staffed = set(&[(2, 4), (6, 8), (20, 30)]);
+
+        assert_eq!(staffed.gaps(span(0, 24)), set(&[(0, 2), (4, 6), (8, 20)]));
+        assert_eq!(IntervalSet::new().gaps(span(0, 24)), set(&[(0, 24)]));
+        assert!(set(&[(0, 24)]).gaps(span(3, 5)).is_empty());
+    }
+}
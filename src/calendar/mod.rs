@@ -0,0 +1,322 @@
+pub mod ical;
+pub mod interval;
+pub mod report;
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday::{self, Fri, Mon, Sat, Sun, Thu, Tue, Wed},
+};
+use interval::{Interval, IntervalSet};
+
+use crate::tz::zone::Zone;
+
+pub const MONDAY_TO_FRIDAY: [Weekday; 5] = [Mon, Tue, Wed, Thu, Fri];
+pub const SUNDAY_TO_THURSDAY: [Weekday; 5] = [Sun, Mon, Tue, Wed, Thu];
+pub const MONDAY_TO_SATURDAY: [Weekday; 6] = [Mon, Tue, Wed, Thu, Fri, Sat];
+
+/// A stretch of local working time starting at `start` on each work day.
+/// Shifts may run past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shift {
+    pub start: NaiveTime,
+    pub length: Duration,
+}
+
+impl Shift {
+    /// A shift from `start` to `end`; an `end` at or before `start` finishes
+    /// on the following day.
+    pub fn between(start: NaiveTime, end: NaiveTime) -> Self {
+        let mut length = end - start;
+        if length <= Duration::zero() {
+            length += Duration::days(1);
+        }
+
+        Self { start, length }
+    }
+
+    pub fn all_day() -> Self {
+        Self {
+            start: NaiveTime::MIN,
+            length: Duration::days(1),
+        }
+    }
+
+    fn contains(&self, time: NaiveDateTime, date: NaiveDate) -> bool {
+        let start = date.and_time(self.start);
+        start <= time && time < start + self.length
+    }
+}
+
+/// The working hours of an office or customer in its own time zone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusinessCalendar {
+    pub timezone: Zone,
+    work_days: Vec<Weekday>,
+    shifts: Vec<Shift>,
+    holidays: Vec<NaiveDate>,
+}
+
+impl BusinessCalendar {
+    /// A Monday–Friday calendar with no shifts and no holidays.
+    pub fn new(timezone: Zone) -> Self {
+        Self {
+            timezone,
+            work_days: MONDAY_TO_FRIDAY.to_vec(),
+            shifts: Vec::new(),
+            holidays: Vec::new(),
+        }
+    }
+
+    pub fn with_work_days(mut self, work_days: &[Weekday]) -> Self {
+        self.work_days = work_days.to_vec();
+        self
+    }
+
+    pub fn with_shift(mut self, shift: Shift) -> Self {
+        self.shifts.push(shift);
+        self
+    }
+
+    pub fn with_holidays(mut self, holidays: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.holidays.extend(holidays);
+        self.holidays.sort();
+        self.holidays.dedup();
+        self
+    }
+
+    /// The same calendar with every holiday worked as a regular day.
+    pub fn without_holidays(&self) -> Self {
+        Self {
+            holidays: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    pub fn work_days(&self) -> &[Weekday] {
+        &self.work_days
+    }
+
+    pub fn shifts(&self) -> &[Shift] {
+        &self.shifts
+    }
+
+    pub fn holidays(&self) -> &[NaiveDate] {
+        &self.holidays
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.binary_search(&date).is_ok()
+    }
+
+    pub fn is_work_day(&self, date: NaiveDate) -> bool {
+        self.work_days.contains(&date.weekday()) && !self.is_holiday(date)
+    }
+
+    /// Whether `instant` falls within one of the shifts, where a shift belongs
+    /// to the local date it starts on.
+    pub fn is_working(&self, instant: &DateTime<Utc>) -> bool {
+        let local = instant.with_timezone(&self.timezone).naive_local();
+        let longest = self
+            .shifts
+            .iter()
+            .map(|s| s.length)
+            .max()
+            .unwrap_or_default();
+
+        local
+            .date()
+            .iter_days()
+            .rev()
+            .take_while(|date| date.and_time(NaiveTime::MIN) + Duration::days(1) + longest > local)
+            .filter(|date| self.is_work_day(*date))
+            .any(|date| self.shifts.iter().any(|shift| shift.contains(local, date)))
+    }
+
+    /// Every working instant inside `window`, built by converting each shift's
+    /// local start and end to UTC.
+    pub fn working_intervals(&self, window: Interval) -> IntervalSet {
+        if window.is_empty() {
+            return IntervalSet::new();
+        }
+
+        let first = window.start.with_timezone(&self.timezone).date_naive() - Duration::days(2);
+        let last = window.end.with_timezone(&self.timezone).date_naive() + Duration::days(1);
+
+        first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .filter(|date| self.is_work_day(*date))
+            .flat_map(|date| {
+                self.shifts.iter().map(move |shift| {
+                    let start = date.and_time(shift.start);
+                    Interval::new(
+                        resolve_local(&self.timezone, start),
+                        resolve_local(&self.timezone, start + shift.length),
+                    )
+                })
+            })
+            .filter_map(|interval| interval.intersection(&window))
+            .collect()
+    }
+
+    /// The UTC span from local midnight on `start` to local midnight on `end`.
+    pub fn local_span(&self, start: NaiveDate, end: NaiveDate) -> Interval {
+        Interval::new(
+            resolve_local(&self.timezone, start.and_time(NaiveTime::MIN)),
+            resolve_local(&self.timezone, end.and_time(NaiveTime::MIN)),
+        )
+    }
+
+    /// The parts of `unstaffed` that fall within this calendar's working time.
+    pub fn overtime(&self, unstaffed: &IntervalSet, window: Interval) -> IntervalSet {
+        self.working_intervals(window).intersection(unstaffed)
+    }
+}
+
+/// The union of the working time of every calendar inside `window`.
+pub fn coverage<'a>(
+    calendars: impl IntoIterator<Item = &'a BusinessCalendar>,
+    window: Interval,
+) -> IntervalSet {
+    calendars
+        .into_iter()
+        .fold(IntervalSet::new(), |covered, calendar| {
+            covered.union(&calendar.working_intervals(window))
+        })
+}
+
+/// The parts of `window` that none of the calendars cover.
+pub fn gaps<'a>(
+    calendars: impl IntoIterator<Item = &'a BusinessCalendar>,
+    window: Interval,
+) -> IntervalSet {
+    coverage(calendars, window).gaps(window)
+}
+
+/// The first UTC instant whose local time is at or after `local`. Local times
+/// skipped by a forward transition resolve to the transition itself, and
+/// repeated local times resolve to their first occurrence.
+fn resolve_local(timezone: &Zone, local: NaiveDateTime) -> DateTime<Utc> {
+    let mut candidate = local;
+
+    loop {
+        match timezone.from_local_datetime(&candidate) {
+            LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => {
+                return datetime.to_utc()
+            }
+            LocalResult::None => candidate += Duration::minutes(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn calendar(zone: &str, start: NaiveTime, end: NaiveTime) -> BusinessCalendar {
+        BusinessCalendar::new(zone.parse().unwrap())
+            .with_work_days(&[Mon, Tue, Wed, Thu, Fri, Sat, Sun])
+            .with_shift(Shift::between(start, end))
+    }
+
+    fn working(
+        calendar: &BusinessCalendar,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<Interval> {
+        calendar
+            .working_intervals(Interval::new(start, end))
+            .intervals()
+            .to_vec()
+    }
+
+    #[test]
+    fn spring_forward_gap() {
+        // Berlin skips from 02:00 to 03:00 on 27 March 2022, at 01:00 UTC.
+        let night = calendar("Europe/Berlin", time(1, 0), time(4, 0));
+        assert_eq!(
+            working(&night, utc(3, 26, 12, 0), utc(3, 27, 12, 0)),
+            vec![Interval::new(utc(3, 27, 0, 0), utc(3, 27, 2, 0))]
+        );
+
+        // A shift starting in the gap starts when the clocks go forward.
+        let skipped = calendar("Europe/Berlin", time(2, 30), time(5, 0));
+        assert_eq!(
+            working(&skipped, utc(3, 26, 12, 0), utc(3, 27, 12, 0)),
+            vec![Interval::new(utc(3, 27, 1, 0), utc(3, 27, 3, 0))]
+        );
+    }
+
+    #[test]
+    fn fall_back_overlap() {
+        // Berlin repeats 02:00 to 03:00 on 30 October 2022, from 00:00 UTC.
+        let night = calendar("Europe/Berlin", time(1, 0), time(4, 0));
+        assert_eq!(
+            working(&night, utc(10, 29, 12, 0), utc(10, 30, 12, 0)),
+            vec![Interval::new(utc(10, 29, 23, 0), utc(10, 30, 3, 0))]
+        );
+
+        // A repeated local time starts at its first occurrence.
+        let repeated = calendar("Europe/Berlin", time(2, 30), time(3, 30));
+        assert_eq!(
+            working(&repeated, utc(10, 29, 12, 0), utc(10, 30, 12, 0)),
+            vec![Interval::new(utc(10, 30, 0, 30), utc(10, 30, 2, 30))]
+        );
+    }
+
+    #[test]
+    fn offset_of_45_minutes() {
+        let office = calendar("Asia/Kathmandu", time(9, 0), time(17, 0));
+
+        assert_eq!(
+            working(&office, utc(1, 3, 0, 0), utc(1, 4, 0, 0)),
+            vec![Interval::new(utc(1, 3, 3, 15), utc(1, 3, 11, 15))]
+        );
+        assert!(office.is_working(&utc(1, 3, 3, 15)));
+        assert!(!office.is_working(&utc(1, 3, 3, 14)));
+    }
+
+    #[test]
+    fn shift_crossing_midnight() {
+        let night = calendar("Europe/London", time(22, 0), time(6, 0)).with_work_days(&[Fri]);
+
+        assert_eq!(
+            working(&night, utc(1, 1, 0, 0), utc(1, 15, 0, 0)),
+            vec![
+                // The tail of the shift that began on Friday 31 December.
+                Interval::new(utc(1, 1, 0, 0), utc(1, 1, 6, 0)),
+                Interval::new(utc(1, 7, 22, 0), utc(1, 8, 6, 0)),
+                Interval::new(utc(1, 14, 22, 0), utc(1, 15, 0, 0)),
+            ]
+        );
+        // Early on Saturday still belongs to Friday's shift.
+        assert!(night.is_working(&utc(1, 8, 2, 0)));
+        assert!(!night.is_working(&utc(1, 9, 2, 0)));
+    }
+
+    #[test]
+    fn holidays_remove_days() {
+        let epiphany = NaiveDate::from_ymd_opt(2022, 1, 6).unwrap();
+        let office = BusinessCalendar::new("Europe/Berlin".parse().unwrap())
+            .with_shift(Shift::between(time(9, 0), time(17, 0)))
+            .with_holidays([epiphany]);
+        let week = Interval::new(utc(1, 3, 0, 0), utc(1, 10, 0, 0));
+
+        assert_eq!(office.working_intervals(week).intervals().len(), 4);
+        assert_eq!(office.working_intervals(week).total(), Duration::hours(32));
+        assert!(!office.is_working(&utc(1, 6, 10, 0)));
+        assert_eq!(
+            office.without_holidays().working_intervals(week).total(),
+            Duration::hours(40)
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, SecondsFormat, Utc};
+
+use super::{
+    interval::{Interval, IntervalSet},
+    BusinessCalendar,
+};
+
+/// Overtime worked for one customer in one month of the customer's local
+/// calendar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyOvertime {
+    pub customer: String,
+    pub month: NaiveDate,
+    pub minutes: i64,
+}
+
+/// An unstaffed window that falls inside an office's regular shifts, so the
+/// office would have covered it had it not been on holiday.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverableWindow {
+    pub office: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+pub fn monthly_overtime(
+    customers: &[(String, BusinessCalendar)],
+    unstaffed: &IntervalSet,
+    window: Interval,
+) -> Vec<MonthlyOvertime> {
+    customers
+        .iter()
+        .flat_map(|(name, customer)| {
+            let overtime = customer.overtime(unstaffed, window);
+            let timezone = &customer.timezone;
+
+            let first = window.start.with_timezone(timezone).date_naive();
+            let last = (window.end - chrono::Duration::seconds(1))
+                .with_timezone(timezone)
+                .date_naive();
+
+            months(first, last).map(move |month| {
+                let next = month + Months::new(1);
+                let span = IntervalSet::from_intervals([customer.local_span(month, next)]);
+
+                MonthlyOvertime {
+                    customer: name.clone(),
+                    month,
+                    minutes: overtime.intersection(&span).total().num_minutes(),
+                }
+            })
+        })
+        .collect()
+}
+
+pub fn coverable_windows(
+    offices: &[(String, BusinessCalendar)],
+    unstaffed: &IntervalSet,
+    window: Interval,
+) -> Vec<CoverableWindow> {
+    offices
+        .iter()
+        .flat_map(|(name, office)| {
+            office
+                .without_holidays()
+                .working_intervals(window)
+                .intersection(unstaffed)
+                .iter()
+                .map(|interval| CoverableWindow {
+                    office: name.clone(),
+                    start: interval.start,
+                    end: interval.end,
+                })
+                .collect::<Vec<CoverableWindow>>()
+        })
+        .collect()
+}
+
+pub fn monthly_overtime_csv(rows: &[MonthlyOvertime]) -> String {
+    let mut csv = String::from("customer,month,overtime_minutes\n");
+
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&row.customer),
+            row.month.format("%Y-%m"),
+            row.minutes
+        ));
+    }
+
+    csv
+}
+
+pub fn coverable_windows_csv(rows: &[CoverableWindow]) -> String {
+    let mut csv = String::from("office,start,end,minutes\n");
+
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&row.office),
+            row.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+            row.end.to_rfc3339_opts(SecondsFormat::Secs, true),
+            (row.end - row.start).num_minutes()
+        ));
+    }
+
+    csv
+}
+
+/// Quotes a field if it contains a delimiter, quote or line break, as
+/// described in RFC 4180.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The first day of every month from the month containing `first` to the one
+/// containing `last`.
+fn months(first: NaiveDate, last: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    let start = first.with_day(1).unwrap();
+
+    std::iter::successors(Some(start), |month| Some(*month + Months::new(1)))
+        .take_while(move |month| *month <= last)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveTime, TimeZone};
+
+    use super::*;
+    use crate::calendar::{coverage, Shift};
+
+    type Calendars = Vec<(String, BusinessCalendar)>;
+
+    /// An office in Berlin, on holiday on Monday 3 January 2022, and a
+    /// customer in UTC working around the clock on weekdays.
+    fn calendars() -> (Calendars, Calendars) {
+        let office = BusinessCalendar::new("Europe/Berlin".parse().unwrap())
+            .with_shift(Shift::between(
+                NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            ))
+            .with_holidays([NaiveDate::from_ymd_opt(2022, 1, 3).unwrap()]);
+        let customer = BusinessCalendar::new("UTC".parse().unwrap()).with_shift(Shift::all_day());
+
+        (
+            vec![("Berlin".to_string(), office)],
+            vec![("Acme, Inc.".to_string(), customer)],
+        )
+    }
+
+    fn window() -> Interval {
+        Interval::new(
+            Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2022, 3, 1, 0, 0, 0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn overtime_per_month() {
+        let (offices, customers) = calendars();
+        let unstaffed = coverage(offices.iter().map(|(_, office)| office), window()).gaps(window());
+
+        // 21 and 20 weekdays of 24 hours, less 8 staffed hours on all but the
+        // holiday.
+        assert_eq!(
+            monthly_overtime_csv(&monthly_overtime(&customers, &unstaffed, window())),
+            "customer,month,overtime_minutes\n\
+             \"Acme, Inc.\",2022-01,20640\n\
+             \"Acme, Inc.\",2022-02,19200\n"
+        );
+    }
+
+    #[test]
+    fn windows_an_office_could_have_covered() {
+        let (offices, _) = calendars();
+        let unstaffed = coverage(offices.iter().map(|(_, office)| office), window()).gaps(window());
+
+        assert_eq!(
+            coverable_windows_csv(&coverable_windows(&offices, &unstaffed, window())),
+            "office,start,end,minutes\n\
+             Berlin,2022-01-03T08:00:00Z,2022-01-03T16:00:00Z,480\n"
+        );
+    }
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
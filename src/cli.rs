@@ -0,0 +1,135 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Error, ErrorKind, Result},
+};
+
+/// Options that take a value, as `--name value` or `--name=value`.
+const VALUED: [&str; 13] = [
+    "codepage", "depth", "format", "from", "ics", "output", "pipeline", "report", "to", "version",
+    "versions", "view", "zones",
+];
+
+/// Options that never take a value, so the argument after them is left for
+/// the next option or the positional values.
+const SWITCHES: [&str; 1] = ["iso"];
+
+/// Command-line arguments split into positional values, `--name value` (or
+/// `--name=value`) options and value-less `--name` flags.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    positional: Vec<String>,
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Options {
+    /// Fails on an option in neither [`VALUED`] nor [`SWITCHES`], and on a
+    /// valued option without its value, rather than guessing which of the
+    /// arguments after it are positional.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = args.iter().peekable();
+
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                options.positional.push(arg.clone());
+                continue;
+            };
+
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+
+            if SWITCHES.contains(&name) {
+                if value.is_some() {
+                    return Err(invalid(format!("--{} does not take a value", name)));
+                }
+                options.flags.insert(name.to_string());
+                continue;
+            }
+
+            if !VALUED.contains(&name) {
+                return Err(invalid(format!("Unknown option --{}", name)));
+            }
+
+            let value = match value {
+                Some(value) => value,
+                None => args
+                    .next_if(|next| !next.starts_with("--"))
+                    .cloned()
+                    .ok_or_else(|| invalid(format!("--{} needs a value", name)))?,
+            };
+            options.values.insert(name.to_string(), value);
+        }
+
+        Ok(options)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Whether the switch `--name` was given.
+    pub fn is_set(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// Whether `--name` was given at all, with or without a value.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name) || self.flags.contains(name)
+    }
+
+    /// A comma-separated option as a list of its non-empty entries.
+    pub fn get_list(&self, name: &str) -> Option<Vec<&str>> {
+        self.get(name)
+            .map(|value| value.split(',').filter(|v| !v.is_empty()).collect())
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Options::parse(&args)
+    }
+
+    #[test]
+    fn values_switches_and_positionals() {
+        let options = parse(&["7", "--iso", "15", "--zones", "UTC;CET", "--from=2022"]).unwrap();
+
+        assert_eq!(options.positional(), ["7", "15"]);
+        assert!(options.is_set("iso"));
+        assert_eq!(options.get("zones"), Some("UTC;CET"));
+        assert_eq!(options.get("from"), Some("2022"));
+        assert!(options.contains("from") && !options.contains("to"));
+    }
+
+    #[test]
+    fn lists() {
+        let options = parse(&["--versions", "2018c,,2023d"]).unwrap();
+
+        assert_eq!(options.get_list("versions"), Some(vec!["2018c", "2023d"]));
+        assert_eq!(options.get_list("version"), None);
+    }
+
+    #[test]
+    fn unknown_options_and_missing_values() {
+        let error = |args: &[&str]| parse(args).unwrap_err().to_string();
+
+        assert_eq!(error(&["--verbose", "15"]), "Unknown option --verbose");
+        assert_eq!(error(&["16", "--output"]), "--output needs a value");
+        assert_eq!(error(&["--output", "--iso"]), "--output needs a value");
+        assert_eq!(error(&["--iso=yes"]), "--iso does not take a value");
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result},
+};
+
+use unicode_normalization::UnicodeNormalization;
+
+/// How words and clues are brought to a common form before comparing them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Folding {
+    /// Compare in NFC, so precomposed and decomposed letters match.
+    pub normalize: bool,
+    /// Compare in lowercase.
+    pub ignore_case: bool,
+}
+
+impl Folding {
+    pub const EXACT: Folding = Folding {
+        normalize: false,
+        ignore_case: false,
+    };
+
+    pub const LOOSE: Folding = Folding {
+        normalize: true,
+        ignore_case: true,
+    };
+
+    pub fn fold(&self, text: &str) -> Vec<char> {
+        let text = match self.ignore_case {
+            true => text.to_lowercase(),
+            false => text.to_string(),
+        };
+
+        match self.normalize {
+            true => text.nfc().collect(),
+            false => text.chars().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Any,
+    Letter(char),
+    /// `[abc]`, `[a-z]` or, when negated, `[^aeiou]`.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Cell {
+    pub fn matches(&self, letter: char) -> bool {
+        match self {
+            Cell::Any => true,
+            Cell::Letter(expected) => *expected == letter,
+            Cell::Class { negated, ranges } => {
+                ranges
+                    .iter()
+                    .any(|(first, last)| (*first..=*last).contains(&letter))
+                    != *negated
+            }
+        }
+    }
+}
+
+/// A crossword clue: one cell per letter, where `.` matches any letter, `[...]`
+/// matches a character class and `\` makes the next character literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    cells: Vec<Cell>,
+}
+
+impl Pattern {
+    pub fn parse(clue: &str, folding: Folding) -> Result<Self> {
+        let mut chars = folding.fold(clue).into_iter();
+        let mut cells = Vec::new();
+
+        while let Some(c) = chars.next() {
+            let cell = match c {
+                '.' => Cell::Any,
+                '\\' => Cell::Letter(chars.next().ok_or_else(|| invalid(clue))?),
+                '[' => {
+                    let mut class: Vec<char> = Vec::new();
+                    loop {
+                        match chars.next().ok_or_else(|| invalid(clue))? {
+                            ']' => break,
+                            '\\' => class.push(chars.next().ok_or_else(|| invalid(clue))?),
+                            c => class.push(c),
+                        }
+                    }
+
+                    let negated = class.first() == Some(&'^');
+                    if negated {
+                        class.remove(0);
+                    }
+
+                    let mut ranges = Vec::new();
+                    let mut i = 0;
+                    while i < class.len() {
+                        match class.get(i + 1..=i + 2) {
+                            Some(['-', last]) => {
+                                ranges.push((class[i], *last));
+                                i += 3;
+                            }
+                            _ => {
+                                ranges.push((class[i], class[i]));
+                                i += 1;
+                            }
+                        }
+                    }
+
+                    Cell::Class { negated, ranges }
+                }
+                c => Cell::Letter(c),
+            };
+
+            cells.push(cell);
+        }
+
+        Ok(Pattern { cells })
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn matches(&self, word: &[char]) -> bool {
+        word.len() == self.cells.len()
+            && self
+                .cells
+                .iter()
+                .zip(word)
+                .all(|(cell, letter)| cell.matches(*letter))
+    }
+}
+
+/// Words indexed by length and by the letter at each position, so a clue only
+/// looks at the words sharing its rarest fixed letter.
+pub struct WordIndex {
+    folding: Folding,
+    words: Vec<Vec<char>>,
+    by_length: HashMap<usize, Vec<usize>>,
+    by_letter: HashMap<(usize, usize, char), Vec<usize>>,
+}
+
+impl WordIndex {
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> Self {
+        Self::with_folding(words, Folding::EXACT)
+    }
+
+    pub fn with_folding<S: AsRef<str>>(
+        words: impl IntoIterator<Item = S>,
+        folding: Folding,
+    ) -> Self {
+        let words: Vec<Vec<char>> = words
+            .into_iter()
+            .map(|word| folding.fold(word.as_ref()))
+            .collect();
+
+        let mut by_length: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut by_letter: HashMap<(usize, usize, char), Vec<usize>> = HashMap::new();
+
+        for (i, word) in words.iter().enumerate() {
+            by_length.entry(word.len()).or_default().push(i);
+
+            for (position, letter) in word.iter().enumerate() {
+                by_letter
+                    .entry((word.len(), position, *letter))
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        WordIndex {
+            folding,
+            words,
+            by_length,
+            by_letter,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The indices of every word matching `clue`, in word order.
+    pub fn matches(&self, clue: &str) -> Result<Vec<usize>> {
+        Ok(self.matches_pattern(&Pattern::parse(clue, self.folding)?))
+    }
+
+    /// The index of the first word matching `clue`.
+    pub fn first_match(&self, clue: &str) -> Result<Option<usize>> {
+        Ok(self.matches(clue)?.first().copied())
+    }
+
+    pub fn matches_pattern(&self, pattern: &Pattern) -> Vec<usize> {
+        let length = pattern.len();
+
+        let candidates = pattern
+            .cells()
+            .iter()
+            .enumerate()
+            .filter_map(|(position, cell)| match cell {
+                Cell::Letter(letter) => Some(
+                    self.by_letter
+                        .get(&(length, position, *letter))
+                        .map_or(&[][..], Vec::as_slice),
+                ),
+                _ => None,
+            })
+            .min_by_key(|candidates| candidates.len())
+            .or_else(|| self.by_length.get(&length).map(Vec::as_slice))
+            .unwrap_or_default();
+
+        candidates
+            .iter()
+            .copied()
+            .filter(|&i| pattern.matches(&self.words[i]))
+            .collect()
+    }
+}
+
+fn invalid(clue: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("Unterminated class or escape in clue {:?}", clue),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 5] = ["Öl", "CAFÉ", "cafe\u{301}", "Zoë", "naïve"];
+
+    #[test]
+    fn exact_folding_keeps_case_and_composition() {
+        let index = WordIndex::new(WORDS);
+
+        assert_eq!(index.matches("CAFÉ").unwrap(), [1]);
+        assert!(index.matches("café").unwrap().is_empty());
+        assert_eq!(index.matches("cafe\u{301}").unwrap(), [2]);
+        assert_eq!(index.matches("ö.").unwrap(), []);
+    }
+
+    #[test]
+    fn loose_folding_ignores_case_and_composition() {
+        let index = WordIndex::with_folding(WORDS, Folding::LOOSE);
+
+        assert_eq!(index.matches("café").unwrap(), [1, 2]);
+        assert_eq!(index.matches("CAFE\u{301}").unwrap(), [1, 2]);
+        assert_eq!(index.matches("ö.").unwrap(), [0]);
+
+        // Diacritics are folded together only when composed differently,
+        // never stripped.
+        assert!(index.matches("cafe").unwrap().is_empty());
+        assert!(index.matches("zoe").unwrap().is_empty());
+    }
+
+    #[test]
+    fn clues_with_accented_letters() {
+        let index = WordIndex::with_folding(WORDS, Folding::LOOSE);
+
+        assert_eq!(index.matches("..ë").unwrap(), [3]);
+        assert_eq!(index.matches("na[ïi]ve").unwrap(), [4]);
+        assert_eq!(index.matches("caf[^e]").unwrap(), [1, 2]);
+        assert_eq!(index.first_match(".[à-ÿ]..").unwrap(), None);
+        assert_eq!(index.first_match("...[à-ÿ]").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn clues_of_the_wrong_length() {
+        let index = WordIndex::with_folding(WORDS, Folding::LOOSE);
+
+        assert!(index.matches("caf").unwrap().is_empty());
+        assert!(index.matches("cafés").unwrap().is_empty());
+        assert!(index.matches("......").unwrap().is_empty());
+        assert_eq!(index.matches(".....").unwrap(), [4]);
+    }
+
+    #[test]
+    fn unterminated_clues_are_errors() {
+        let index = WordIndex::new(WORDS);
+
+        assert!(index.matches("caf[é").is_err());
+        assert!(index.matches("caf\\").is_err());
+    }
+}
@@ -4,19 +4,20 @@ use std::{
 };
 
 use chrono::{DateTime, NaiveDateTime, TimeZone};
-use chrono_tz::Tz;
+
+use crate::tz::zone::Zone;
 
 pub fn solve() -> Result<String> {
     let mut input = String::new();
     BufReader::new(File::open("./input/04.txt")?).read_to_string(&mut input)?;
 
     let travel_time = input.trim().split("\n\n").fold(0, |total, trip| {
-        let times: Vec<DateTime<Tz>> = trip
+        let times: Vec<DateTime<Zone>> = trip
             .split("\n")
             .map(|location| {
                 let parts = location.split_whitespace().collect::<Vec<&str>>();
 
-                let timezone: Tz = parts[1].parse().unwrap();
+                let timezone: Zone = parts[1].parse().unwrap();
 
                 let naive_time = NaiveDateTime::parse_from_str(
                     &(parts[2..].join(" ") + ":00"),
@@ -28,7 +29,9 @@ pub fn solve() -> Result<String> {
             })
             .collect();
 
-        total + times[1].signed_duration_since(times[0]).num_minutes()
+        let [departure, arrival]: [DateTime<Zone>; 2] = times.try_into().unwrap();
+
+        total + arrival.signed_duration_since(departure).num_minutes()
     });
 
     Ok(format!("The total travel time is {} minutes.", travel_time))
@@ -1,22 +1,28 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Result},
+    io::{BufReader, Read, Result},
 };
 
+use crate::grid::Grid;
+
 pub fn solve() -> Result<String> {
-    let lines = BufReader::new(File::open("./input/05.txt")?).lines();
+    let mut input = String::new();
+    BufReader::new(File::open("./input/05.txt")?).read_to_string(&mut input)?;
+
+    let grid = Grid::from_text(&input);
+
+    // Each step wraps around the line it lands on, not the padded grid, since
+    // the lines need not be the same width.
+    let widths: Vec<usize> = input.lines().map(|line| line.chars().count()).collect();
 
-    let (poops, _) = lines
-        .skip(1)
-        .map_while(Result::ok)
-        .fold((0, 0), |(poops, x_pos), line| {
-            let next_pos = (x_pos + 2) % line.chars().count();
+    let (poops, _) = (1..grid.rows()).fold((0, 0), |(poops, col), row| {
+        let col = (col + 2) % widths[row];
 
-            match line.chars().nth(next_pos) {
-                Some('💩') => (poops + 1, next_pos),
-                _ => (poops, next_pos),
-            }
-        });
+        match grid[(row, col)] {
+            '💩' => (poops + 1, col),
+            _ => (poops, col),
+        }
+    });
 
     Ok(format!("You step in poop {} times.", poops))
 }
@@ -1,47 +1,85 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Result},
+    io::{BufRead, BufReader, Error, ErrorKind, Result},
 };
 
-use chrono::{Duration, NaiveDateTime, TimeZone, Timelike};
-use chrono_tz::Tz;
+use chrono::{DateTime, Duration};
 
-pub fn solve() -> Result<String> {
+use crate::{
+    cli::Options,
+    tz::{
+        correction::{
+            correct_all, correction_between, to_iso8601_lines, weighted_hour_sum, CorrectedRecord,
+        },
+        resolver::ZoneResolver,
+        zone::Zone,
+    },
+};
+
+const CANDIDATE_ZONES: [&str; 2] = ["America/Santiago", "America/Halifax"];
+
+/// The candidate zones from `--zones`, separated by `;` since POSIX TZ strings
+/// contain commas.
+fn candidate_zones(options: &Options) -> Result<Vec<Zone>> {
+    match options.get("zones") {
+        Some(zones) => zones
+            .split(';')
+            .filter(|zone| !zone.is_empty())
+            .map(str::parse)
+            .collect(),
+        None => CANDIDATE_ZONES.iter().map(|zone| zone.parse()).collect(),
+    }
+}
+
+fn read_log(zones: Vec<Zone>) -> Result<Vec<CorrectedRecord<Zone>>> {
     let lines = BufReader::new(File::open("./input/07.txt")?).lines();
-    let halifax_timezone: Tz = "America/Halifax".parse().unwrap();
-    let santiago_timezone: Tz = "America/Santiago".parse().unwrap();
+    let resolver = ZoneResolver::new(zones);
 
-    let fixed_times: Vec<NaiveDateTime> = lines
+    let records = lines
         .map_while(Result::ok)
         .map(|line| {
             let parts = line.split_whitespace().collect::<Vec<&str>>();
 
-            let (naive_time_str, offset_str) = parts[0].split_once(".000").unwrap_or(("", ""));
-            let naive_time =
-                NaiveDateTime::parse_from_str(naive_time_str, "%Y-%m-%dT%H:%M:%S").unwrap();
+            let record = DateTime::parse_from_rfc3339(parts[0])
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
-            let halifax_time = halifax_timezone.from_local_datetime(&naive_time).unwrap();
-            let santiago_time = santiago_timezone.from_local_datetime(&naive_time).unwrap();
+            let time = resolver
+                .resolve(&record)
+                .preferred()
+                .cloned()
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("No candidate zone matches {}", parts[0]),
+                    )
+                })?;
 
-            let mut time = if offset_str.starts_with(&santiago_time.offset().to_string()) {
-                santiago_time
-            } else {
-                halifax_time
-            };
+            let correction = correction_between(
+                Duration::minutes(parts[1].parse().unwrap()),
+                Duration::minutes(parts[2].parse().unwrap()),
+            );
 
-            time -= Duration::minutes(parts[2].parse().unwrap());
-            time += Duration::minutes(parts[1].parse().unwrap());
-
-            time.naive_local()
+            Ok((time, correction))
         })
-        .collect::<Vec<NaiveDateTime>>();
-
-    let hour_sum = fixed_times
-        .iter()
-        .enumerate()
-        .fold(0, |sum, (i, naive_time)| {
-            sum + (i + 1) as u32 * naive_time.hour()
-        });
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(correct_all(records))
+}
+
+pub fn solve() -> Result<String> {
+    solve_with(&Options::default())
+}
+
+/// `--iso` lists the corrected audit log, one ISO 8601 timestamp per line,
+/// instead of summing its hours.
+pub fn solve_with(options: &Options) -> Result<String> {
+    let log = read_log(candidate_zones(options)?)?;
+
+    if options.is_set("iso") {
+        return Ok(to_iso8601_lines(&log));
+    }
+
+    let hour_sum = weighted_hour_sum(&log);
 
     Ok(format!("The sum of the local hours is {}.", hour_sum))
 }
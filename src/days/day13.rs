@@ -1,31 +1,13 @@
-use core::str;
 use std::{
     fs::File,
-    io::{BufReader, Read, Result},
+    io::{BufReader, Error, Read, Result},
 };
 
-use encoding_rs::mem::decode_latin1;
-
-trait Latin {
-    fn is_latin(&self) -> bool;
-}
-
-impl Latin for String {
-    fn is_latin(&self) -> bool {
-        self.chars().all(|c| {
-            ('\u{0041}'..='\u{007A}').contains(&c) || // Basic Latin
-                ('\u{00C0}'..='\u{00FF}').contains(&c) // Latin-1 Supplement
-        })
-    }
-}
-
-fn matches_clue(word: &str, clue: &str) -> bool {
-    clue.chars().count() == word.chars().count()
-        && clue
-            .chars()
-            .enumerate()
-            .all(|(i, letter)| letter == '.' || letter == word.chars().nth(i).unwrap())
-}
+use crate::{
+    crossword::WordIndex,
+    encoding::{detect::detect_encoding, hex::decode_hex},
+    script::{plausibility, Plausibility},
+};
 
 pub fn solve() -> Result<String> {
     let mut input = String::new();
@@ -33,54 +15,51 @@ pub fn solve() -> Result<String> {
 
     let (encoded_words, crossword) = input.trim().split_once("\n\n").unwrap_or(("", ""));
 
-    let decoded_words: Vec<String> = encoded_words
+    let decodings: Vec<(Plausibility, String)> = encoded_words
         .lines()
-        .map(|word| -> String {
-            let u8_bytes: Vec<u8> = word
-                .chars()
-                .collect::<Vec<char>>()
-                .chunks(2)
-                .map(|c| c.iter().collect::<String>())
-                .map_while(|c| u8::from_str_radix(&c, 16).ok())
-                .collect::<Vec<u8>>();
-
-            let u16_be_bytes: Vec<u16> = (0..u8_bytes.len() / 2)
-                .map(|i| u16::from_be_bytes([u8_bytes[2 * i], u8_bytes[2 * i + 1]]))
-                .collect();
+        .map(|word| -> Result<(Plausibility, String)> {
+            let u8_bytes = decode_hex(word)?;
 
-            let u16_le_bytes: Vec<u16> = (0..u8_bytes.len() / 2)
-                .map(|i| u16::from_le_bytes([u8_bytes[2 * i], u8_bytes[2 * i + 1]]))
-                .collect();
-
-            match u8_bytes[..] {
-                [0xFE, 0xFF, ..] => String::from_utf16(&u16_be_bytes[1..]).unwrap(),
-                [0xFF, 0xFE, ..] => String::from_utf16(&u16_le_bytes[1..]).unwrap(),
-                [0xEF, 0xBB, 0xBF, ..] => String::from_utf8(u8_bytes[3..].into()).unwrap(),
-                _ => {
-                    match (
-                        String::from_utf8(u8_bytes.clone()),
-                        String::from_utf16(&u16_be_bytes),
-                        String::from_utf16(&u16_le_bytes),
-                    ) {
-                        (Ok(word), _, _) if word.is_latin() => word,
-                        (_, Ok(word), _) if word.is_latin() => word,
-                        (_, _, Ok(word)) if word.is_latin() => word,
-                        _ => decode_latin1(&u8_bytes).into_owned(),
-                    }
-                }
-            }
+            // The most plausible decoding that does not mix scripts wins, ties
+            // going to the charset the detector was more confident in.
+            Ok(detect_encoding(&u8_bytes)
+                .candidates
+                .iter()
+                .filter_map(|candidate| candidate.decode(&u8_bytes))
+                .map(|word| (plausibility(&word), word))
+                .rev()
+                .max_by(|(a, _), (b, _)| {
+                    (!a.is_suspicious())
+                        .cmp(&!b.is_suspicious())
+                        .then(a.score.total_cmp(&b.score))
+                })
+                .expect("detection always has a candidate that decodes"))
         })
-        .collect();
+        .collect::<Result<_>>()?;
+
+    let index = WordIndex::new(decodings.iter().map(|(_, word)| word));
 
-    let score = crossword.lines().fold(0, |score, clue| {
-        match decoded_words
-            .iter()
-            .position(|word| matches_clue(word, clue.trim()))
-        {
+    let score = crossword.lines().try_fold(0, |score, clue| {
+        Ok::<_, Error>(match index.first_match(clue.trim())? {
             Some(position) => score + position + 1,
             None => score,
+        })
+    })?;
+
+    let mut lines = vec![format!("The crossword solution is {}", score)];
+
+    // Words that could only be decoded into a suspicious mix of scripts are
+    // likely wrong, so they are listed for checking by hand.
+    for (line, (plausibility, word)) in decodings.iter().enumerate() {
+        if plausibility.is_suspicious() {
+            lines.push(format!(
+                "   Word {} mixes scripts ({:?}): {}",
+                line + 1,
+                plausibility.restriction,
+                word
+            ));
         }
-    });
+    }
 
-    Ok(format!("The crossword solution is {}", score))
+    Ok(lines.join("\n"))
 }
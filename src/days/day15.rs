@@ -1,92 +1,190 @@
 use std::{
-    fs::File,
-    io::{BufReader, Read, Result},
+    fs::{self, File},
+    io::{BufReader, Error, ErrorKind, Read, Result},
+    ops::RangeInclusive,
+    path::Path,
 };
 
-use chrono::{
-    DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc,
-    Weekday::{Fri, Mon, Thu, Tue, Wed},
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::{
+    calendar::{
+        coverage,
+        ical::{export_intervals, parse_holidays as parse_ics_holidays},
+        interval::{Interval, IntervalSet},
+        report::{
+            coverable_windows, coverable_windows_csv, monthly_overtime, monthly_overtime_csv,
+        },
+        BusinessCalendar, Shift,
+    },
+    cli::Options,
+    tz::zone::Zone,
 };
-use chrono_tz::Tz;
 
-trait Working {
-    fn is_work_day(&self) -> bool;
-    fn is_work_hour(&self) -> bool;
-    fn is_holiday(&self, holidays: &[(u32, u32)]) -> bool;
+/// Reads the holidays column, which is either a `;`-separated list of dates or
+/// the name of an iCalendar file in `./input`.
+fn parse_holidays(field: &str, years: RangeInclusive<i32>) -> Result<Vec<NaiveDate>> {
+    if field.ends_with(".ics") {
+        let ics = fs::read_to_string(Path::new("./input").join(field))?;
+        return parse_ics_holidays(&ics, years);
+    }
+
+    Ok(field
+        .split(';')
+        .map(|date_str| NaiveDate::parse_from_str(date_str, "%d %B %Y").unwrap())
+        .collect())
 }
 
-impl Working for DateTime<Tz> {
-    fn is_work_day(&self) -> bool {
-        [Mon, Tue, Wed, Thu, Fri].contains(&self.weekday())
-    }
+fn parse_line(line: &str, years: RangeInclusive<i32>) -> Result<(String, Zone, Vec<NaiveDate>)> {
+    let parts: Vec<&str> = line.splitn(3, '\t').collect();
 
-    fn is_work_hour(&self) -> bool {
-        (9..=16).contains(&self.hour()) || (self.hour() == 8 && self.minute() >= 30)
-    }
+    let timezone: Zone = parts[1].parse()?;
+    let holidays = parse_holidays(parts[2], years)?;
 
-    fn is_holiday(&self, holidays: &[(u32, u32)]) -> bool {
-        holidays
-            .iter()
-            .any(|(day, month)| self.day() == *day && self.month() == *month)
-    }
+    Ok((parts[0].to_string(), timezone, holidays))
 }
 
-fn parse_line(line: &str) -> (Tz, Vec<(u32, u32)>) {
-    let parts: Vec<&str> = line.splitn(3, '\t').collect();
+fn parse_office(line: &str, years: RangeInclusive<i32>) -> Result<(String, BusinessCalendar)> {
+    let (name, timezone, holidays) = parse_line(line, years)?;
 
-    let timezone: Tz = parts[1].parse().unwrap();
+    let calendar = BusinessCalendar::new(timezone)
+        .with_shift(Shift::between(
+            NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+            NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+        ))
+        .with_holidays(holidays);
 
-    let holidays: Vec<(u32, u32)> = parts[2]
-        .split(';')
-        .map(|date_str| {
-            let dt = NaiveDate::parse_from_str(date_str, "%d %B %Y").unwrap();
-            (dt.day(), dt.month())
-        })
-        .collect();
+    Ok((name, calendar))
+}
+
+fn parse_customer(line: &str, years: RangeInclusive<i32>) -> Result<(String, BusinessCalendar)> {
+    let (name, timezone, holidays) = parse_line(line, years)?;
+
+    let calendar = BusinessCalendar::new(timezone)
+        .with_shift(Shift::all_day())
+        .with_holidays(holidays);
+
+    Ok((name, calendar))
+}
+
+type Calendars = Vec<(String, BusinessCalendar)>;
+
+/// Parses the offices and customers, expanding recurring holidays over the
+/// UTC years `first..=last` and the local days either side of them.
+fn parse_input(input: &str, first: i32, last: i32) -> Result<(Calendars, Calendars)> {
+    let (offices, customers) = input.trim().split_once("\n\n").unwrap();
+    let years = first - 1..=last + 1;
+
+    Ok((
+        offices
+            .lines()
+            .map(|line| parse_office(line, years.clone()))
+            .collect::<Result<_>>()?,
+        customers
+            .lines()
+            .map(|line| parse_customer(line, years.clone()))
+            .collect::<Result<_>>()?,
+    ))
+}
 
-    (timezone, holidays)
+fn unstaffed(offices: &Calendars, window: Interval) -> IntervalSet {
+    coverage(offices.iter().map(|(_, office)| office), window).gaps(window)
+}
+
+/// The per-customer overtime, in minutes, across the UTC years `first..=last`.
+pub fn overtimes(input: &str, first: i32, last: i32) -> Result<Vec<i64>> {
+    let (offices, customers) = parse_input(input, first, last)?;
+
+    let window = Interval::years(first, last);
+    let unstaffed = unstaffed(&offices, window);
+
+    Ok(customers
+        .iter()
+        .map(|(_, customer)| customer.overtime(&unstaffed, window).total().num_minutes())
+        .collect())
+}
+
+/// Writes `customers.csv`, with each customer's overtime per local month, and
+/// `offices.csv`, with the unstaffed windows each office could have covered
+/// had it not been on holiday.
+pub fn write_report(input: &str, first: i32, last: i32, directory: &Path) -> Result<()> {
+    let (offices, customers) = parse_input(input, first, last)?;
+
+    let window = Interval::years(first, last);
+    let unstaffed = unstaffed(&offices, window);
+
+    fs::create_dir_all(directory)?;
+    fs::write(
+        directory.join("customers.csv"),
+        monthly_overtime_csv(&monthly_overtime(&customers, &unstaffed, window)),
+    )?;
+    fs::write(
+        directory.join("offices.csv"),
+        coverable_windows_csv(&coverable_windows(&offices, &unstaffed, window)),
+    )?;
+
+    Ok(())
+}
+
+/// Writes `staffed.ics` and `unstaffed.ics` with the windows in which at least
+/// one office is, or no office is, working.
+pub fn write_calendars(input: &str, first: i32, last: i32, directory: &Path) -> Result<()> {
+    let (offices, _) = parse_input(input, first, last)?;
+
+    let window = Interval::years(first, last);
+    let staffed = coverage(offices.iter().map(|(_, office)| office), window);
+
+    fs::create_dir_all(directory)?;
+    fs::write(
+        directory.join("staffed.ics"),
+        export_intervals(&staffed, "Staffed"),
+    )?;
+    fs::write(
+        directory.join("unstaffed.ics"),
+        export_intervals(&staffed.gaps(window), "Unstaffed"),
+    )?;
+
+    Ok(())
 }
 
 pub fn solve() -> Result<String> {
+    solve_with(&Options::default())
+}
+
+pub fn solve_with(options: &Options) -> Result<String> {
     let mut input = String::new();
     BufReader::new(File::open("./input/15.txt")?).read_to_string(&mut input)?;
 
-    let (offices, customers) = input.trim().split_once("\n\n").unwrap();
-    let offices: Vec<(Tz, Vec<(u32, u32)>)> = offices.lines().map(parse_line).collect();
-
-    let start_timestamp = Utc
-        .with_ymd_and_hms(2022, 1, 1, 0, 0, 0)
-        .unwrap()
-        .timestamp();
-
-    let unstaffed_periods: Vec<DateTime<Utc>> = (0..(2 * 24 * 365))
-        .filter_map(|i| {
-            let timestamp = start_timestamp + (30 * 60 * i);
-            let utc_datetime: DateTime<Utc> = DateTime::from_timestamp(timestamp, 0).unwrap();
-
-            if offices.iter().any(|(timezone, holidays)| {
-                let local = utc_datetime.with_timezone(timezone);
-                local.is_work_day() && local.is_work_hour() && !local.is_holiday(holidays)
-            }) {
-                return None;
-            }
-
-            Some(utc_datetime)
-        })
-        .collect();
-
-    let overtimes: Vec<usize> = customers
-        .lines()
-        .map(parse_line)
-        .map(|(timezone, holidays)| {
-            let overtime_periods = unstaffed_periods.iter().filter(|utc_datetime| {
-                let local = utc_datetime.with_timezone(&timezone);
-                local.is_work_day() && !local.is_holiday(&holidays)
-            });
-
-            overtime_periods.count() * 30
-        })
-        .collect();
+    let year = |name: &str| -> Result<Option<i32>> {
+        options
+            .get(name)
+            .map(|year| {
+                year.parse().map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("--{} must be a year, not {:?}", name, year),
+                    )
+                })
+            })
+            .transpose()
+    };
+    let first = year("from")?.unwrap_or(2022);
+    let last = year("to")?.unwrap_or(first);
+
+    if let Some(directory) = options.get("report") {
+        write_report(&input, first, last, Path::new(directory))?;
+        return Ok(format!("The coverage report was written to {}", directory));
+    }
+
+    if let Some(directory) = options.get("ics") {
+        write_calendars(&input, first, last, Path::new(directory))?;
+        return Ok(format!(
+            "The staffing calendars were written to {}",
+            directory
+        ));
+    }
+
+    let overtimes = overtimes(&input, first, last)?;
 
     Ok(format!(
         "The difference between the highest and lowest amount of overtime per customer is {}",
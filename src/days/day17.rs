@@ -1,9 +1,11 @@
 use std::{
     collections::HashMap,
     fs::File,
-    io::{BufReader, Read, Result},
+    io::{BufReader, Error, ErrorKind, Read, Result},
 };
 
+use crate::{encoding::hex::decode_hex, grid::Grid};
+
 type Fragment = Vec<Vec<u8>>;
 
 #[derive(Clone, Copy)]
@@ -144,25 +146,14 @@ pub fn solve() -> Result<String> {
     let fragments: Vec<Fragment> = input
         .trim()
         .split("\n\n")
-        .map(|f| {
-            f.lines()
-                .map(|line| {
-                    line.chars()
-                        .collect::<Vec<char>>()
-                        .chunks(2)
-                        .map(|c| c.iter().collect::<String>())
-                        .map_while(|c| u8::from_str_radix(&c, 16).ok())
-                        .collect::<Vec<u8>>()
-                })
-                .collect()
-        })
-        .collect();
+        .map(|f| f.lines().map(decode_hex).collect())
+        .collect::<Result<_>>()?;
 
     let top_left = fragments
         .iter()
         .find(|frag| {
             frag.first()
-                .map_or(false, |line| line.starts_with(&[0xe2, 0x95, 0x94]))
+                .is_some_and(|line| line.starts_with(&[0xe2, 0x95, 0x94]))
         })
         .unwrap();
 
@@ -209,13 +200,11 @@ pub fn solve() -> Result<String> {
         }
     }
 
-    let decoded_treasure_map = treasure_map.decode();
+    let decoded_treasure_map = Grid::from_text(&treasure_map.decode().join("\n"));
 
     let (x, y) = decoded_treasure_map
-        .iter()
-        .enumerate()
-        .find_map(|(x, line)| line.chars().position(|c| c == '╳').map(|y| (x, y)))
-        .unwrap();
+        .position(|&c| c == '╳')
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "The treasure map has no ╳"))?;
 
     Ok(format!(
         "The product of the x and y coordinates is {:?}.",
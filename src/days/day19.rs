@@ -4,40 +4,62 @@ use std::{
     io::{BufRead, BufReader, Error, ErrorKind, Result},
 };
 
-use chrono::{LocalResult, NaiveDateTime, TimeZone};
-use tzfile::Tz;
+use chrono::{LocalResult, NaiveDateTime};
+
+use crate::{
+    cli::Options,
+    tz::database::{tzdata_versions, TzDatabase, ZONEINFO_DIR},
+};
 
 pub fn solve() -> Result<String> {
+    solve_with(&Options::default())
+}
+
+pub fn solve_with(options: &Options) -> Result<String> {
+    let available = tzdata_versions();
+
+    let versions = match options.get_list("versions") {
+        Some(versions) => {
+            if let Some(missing) = versions.iter().find(|v| !available.contains(v)) {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "tzdata {} is not available, expected one of {}",
+                        missing,
+                        available.join(", ")
+                    ),
+                ));
+            }
+            versions
+        }
+        None => available,
+    };
+
+    if versions.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            "No tzdata versions available",
+        ));
+    }
+
     let lines = BufReader::new(File::open("./input/19.txt")?)
         .lines()
         .map_while(Result::ok);
 
-    let times = lines
-        .flat_map(|line| {
-            let (naive_time_str, timezone_str) = line.split_once("; ").unwrap();
-            let naive_time =
-                NaiveDateTime::parse_from_str(naive_time_str, "%Y-%m-%d %H:%M:%S").unwrap();
-
-            let mut results: Vec<String> = Vec::new();
+    let database = TzDatabase::open(ZONEINFO_DIR, &versions)?;
+    let mut times: HashMap<String, usize> = HashMap::new();
 
-            for version in &["2018c", "2018g", "2021b", "2023d"] {
-                let path = format!("./input/zoneinfo/{}/{}", version, timezone_str);
-                let source = std::fs::read(path).unwrap();
+    for line in lines {
+        let (naive_time_str, timezone_str) = line.split_once("; ").unwrap();
+        let naive_time =
+            NaiveDateTime::parse_from_str(naive_time_str, "%Y-%m-%d %H:%M:%S").unwrap();
 
-                let timezone = Tz::parse(timezone_str, &source).unwrap();
-
-                if let LocalResult::Single(datetime) = (&timezone).from_local_datetime(&naive_time)
-                {
-                    results.push(datetime.to_utc().format("%+").to_string())
-                }
+        for (_, result) in database.to_utc_all(timezone_str, &naive_time)? {
+            if let LocalResult::Single(datetime) = result {
+                *times.entry(datetime.format("%+").to_string()).or_insert(0) += 1;
             }
-
-            results
-        })
-        .fold(HashMap::new(), |mut map, datetime| {
-            *map.entry(datetime).or_insert(0) += 1;
-            map
-        });
+        }
+    }
 
     if let Some((time, _)) = times.iter().max_by_key(|(_, entry)| **entry) {
         return Ok(format!("The gravitational wave was recorded at {}", time));
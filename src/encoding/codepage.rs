@@ -0,0 +1,239 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use encoding_rs::{Encoding, ISO_8859_15, ISO_8859_2, KOI8_R, MACINTOSH, WINDOWS_1252};
+
+use super::dos::{CONTROL_GLYPHS, CP437, CP850, CP852, CP866, DELETE_GLYPH};
+
+/// A single-byte character set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Codepage {
+    Latin1,
+    Windows1252,
+    Iso8859_2,
+    Iso8859_15,
+    Koi8R,
+    MacRoman,
+    Cp437,
+    Cp850,
+    Cp852,
+    Cp866,
+}
+
+impl Codepage {
+    /// Every codepage but the DOS ones, in the order mojibake detection
+    /// prefers them.
+    pub const ALL: [Codepage; 6] = [
+        Codepage::Latin1,
+        Codepage::Windows1252,
+        Codepage::Iso8859_15,
+        Codepage::Iso8859_2,
+        Codepage::MacRoman,
+        Codepage::Koi8R,
+    ];
+
+    /// The DOS codepages, which are only used when asked for by name. Their
+    /// box drawing and control glyphs decode almost any bytes to something,
+    /// so detection leaves them out.
+    pub const DOS: [Codepage; 4] = [
+        Codepage::Cp437,
+        Codepage::Cp850,
+        Codepage::Cp852,
+        Codepage::Cp866,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codepage::Latin1 => "ISO-8859-1",
+            Codepage::Windows1252 => "Windows-1252",
+            Codepage::Iso8859_2 => "ISO-8859-2",
+            Codepage::Iso8859_15 => "ISO-8859-15",
+            Codepage::Koi8R => "KOI8-R",
+            Codepage::MacRoman => "MacRoman",
+            Codepage::Cp437 => "CP437",
+            Codepage::Cp850 => "CP850",
+            Codepage::Cp852 => "CP852",
+            Codepage::Cp866 => "CP866",
+        }
+    }
+
+    /// Latin-1 has no `encoding_rs` counterpart, since the WHATWG standard
+    /// treats it as an alias of Windows-1252, and the DOS codepages use their
+    /// own tables.
+    fn encoding(&self) -> Option<&'static Encoding> {
+        match self {
+            Codepage::Latin1
+            | Codepage::Cp437
+            | Codepage::Cp850
+            | Codepage::Cp852
+            | Codepage::Cp866 => None,
+            Codepage::Windows1252 => Some(WINDOWS_1252),
+            Codepage::Iso8859_2 => Some(ISO_8859_2),
+            Codepage::Iso8859_15 => Some(ISO_8859_15),
+            Codepage::Koi8R => Some(KOI8_R),
+            Codepage::MacRoman => Some(MACINTOSH),
+        }
+    }
+
+    /// The characters of bytes 0x80–0xFF in the DOS codepages.
+    fn upper_half(&self) -> Option<&'static [char; 128]> {
+        match self {
+            Codepage::Cp437 => Some(&CP437),
+            Codepage::Cp850 => Some(&CP850),
+            Codepage::Cp852 => Some(&CP852),
+            Codepage::Cp866 => Some(&CP866),
+            _ => None,
+        }
+    }
+
+    pub fn is_dos(&self) -> bool {
+        self.upper_half().is_some()
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        if let Some(upper_half) = self.upper_half() {
+            return bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0x00..=0x7F => byte as char,
+                    _ => upper_half[byte as usize - 0x80],
+                })
+                .collect();
+        }
+
+        match self.encoding() {
+            Some(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+            None => bytes.iter().map(|&byte| byte as char).collect(),
+        }
+    }
+
+    /// Decodes like [`Codepage::decode`], except that the DOS codepages show
+    /// control bytes as the glyphs DOS displayed for them, such as `☺` for
+    /// 0x01. Null stays as it is.
+    pub fn decode_with_glyphs(&self, bytes: &[u8]) -> String {
+        if !self.is_dos() {
+            return self.decode(bytes);
+        }
+
+        self.decode(bytes)
+            .chars()
+            .map(|c| match c {
+                '\u{01}'..='\u{1F}' => CONTROL_GLYPHS[c as usize - 1],
+                '\u{7F}' => DELETE_GLYPH,
+                c => c,
+            })
+            .collect()
+    }
+
+    /// The bytes that decode to `text`, or `None` if some character has no
+    /// byte in this codepage.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        text.chars().map(|c| self.encode_char(c)).collect()
+    }
+
+    /// Windows-1252 also takes back the C1 controls a decoder leaves in place
+    /// of its five undefined bytes, and that Latin-1 decoders produce from
+    /// 0x80–0x9F.
+    ///
+    /// The DOS codepages take back their control glyphs as well as the
+    /// control characters themselves, except that `¶` and `§` go to the
+    /// upper half in the codepages that also have them there.
+    pub fn encode_char(&self, c: char) -> Option<u8> {
+        if let Some(upper_half) = self.upper_half() {
+            if c.is_ascii() {
+                return Some(c as u8);
+            }
+
+            return upper_half
+                .iter()
+                .position(|&other| other == c)
+                .map(|i| 0x80 + i as u8)
+                .or_else(|| {
+                    CONTROL_GLYPHS
+                        .iter()
+                        .position(|&glyph| glyph == c)
+                        .map(|i| i as u8 + 1)
+                })
+                .or((c == DELETE_GLYPH).then_some(0x7F));
+        }
+
+        let Some(encoding) = self.encoding() else {
+            return u8::try_from(c).ok();
+        };
+
+        if c.is_ascii() || (*self == Codepage::Windows1252 && ('\u{80}'..='\u{9f}').contains(&c)) {
+            return Some(c as u8);
+        }
+
+        let mut buffer = [0; 4];
+        let (bytes, _, unmappable) = encoding.encode(c.encode_utf8(&mut buffer));
+
+        match (&bytes[..], unmappable) {
+            ([byte], false) => Some(*byte),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Codepage {
+    type Err = Error;
+
+    fn from_str(label: &str) -> Result<Self> {
+        match label.to_ascii_lowercase().replace('_', "-").as_str() {
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Codepage::Latin1),
+            "cp1252" | "windows-1252" => Ok(Codepage::Windows1252),
+            "latin2" | "latin-2" | "iso-8859-2" => Ok(Codepage::Iso8859_2),
+            "latin9" | "latin-9" | "iso-8859-15" => Ok(Codepage::Iso8859_15),
+            "koi8-r" | "koi8r" => Ok(Codepage::Koi8R),
+            "macroman" | "mac-roman" | "macintosh" => Ok(Codepage::MacRoman),
+            "cp437" | "ibm437" | "437" => Ok(Codepage::Cp437),
+            "cp850" | "ibm850" | "850" => Ok(Codepage::Cp850),
+            "cp852" | "ibm852" | "852" => Ok(Codepage::Cp852),
+            "cp866" | "ibm866" | "866" => Ok(Codepage::Cp866),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown codepage {:?}", label),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Codepage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detection_leaves_out_the_dos_codepages() {
+        assert!(Codepage::ALL.iter().all(|codepage| !codepage.is_dos()));
+        assert!(Codepage::DOS.iter().all(Codepage::is_dos));
+    }
+
+    #[test]
+    fn dos_codepages_round_trip_every_byte() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        for codepage in Codepage::DOS {
+            let text = codepage.decode(&bytes);
+            assert_eq!(codepage.encode(&text).as_deref(), Some(&bytes[..]));
+            assert_eq!(codepage.decode_with_glyphs(&[0, 1, 0x7F]), "\0☺⌂");
+        }
+    }
+
+    #[test]
+    fn labels() {
+        for label in ["CP437", "ibm850", "852", "CP866"] {
+            assert!(label.parse::<Codepage>().unwrap().is_dos(), "{}", label);
+        }
+        assert_eq!("latin_1".parse::<Codepage>().unwrap(), Codepage::Latin1);
+        assert!("cp999".parse::<Codepage>().is_err());
+    }
+}
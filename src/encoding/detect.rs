@@ -0,0 +1,333 @@
+use std::fmt;
+
+use crate::script::PUNCTUATION;
+
+use super::codepage::Codepage;
+
+/// A character encoding that bytes may be decoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Charset {
+    Utf8,
+    Utf16Be,
+    Utf16Le,
+    Utf32Be,
+    Utf32Le,
+    SingleByte(Codepage),
+}
+
+impl Charset {
+    /// Charsets with a byte order mark, ordered so that a BOM is never taken
+    /// for a shorter one it starts with.
+    pub const WITH_BOM: [Charset; 5] = [
+        Charset::Utf32Le,
+        Charset::Utf32Be,
+        Charset::Utf8,
+        Charset::Utf16Be,
+        Charset::Utf16Le,
+    ];
+
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Charset::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Charset::Utf16Be => &[0xFE, 0xFF],
+            Charset::Utf16Le => &[0xFF, 0xFE],
+            Charset::Utf32Be => &[0x00, 0x00, 0xFE, 0xFF],
+            Charset::Utf32Le => &[0xFF, 0xFE, 0x00, 0x00],
+            Charset::SingleByte(_) => &[],
+        }
+    }
+
+    /// Decodes `bytes`, without a BOM, failing on anything malformed.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Charset::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Charset::Utf16Be | Charset::Utf16Le => {
+                let units: Vec<u16> = bytes
+                    .chunks(2)
+                    .map(|pair| match (self, pair) {
+                        (Charset::Utf16Be, [high, low]) => Some(u16::from_be_bytes([*high, *low])),
+                        (_, [low, high]) => Some(u16::from_le_bytes([*low, *high])),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+
+                String::from_utf16(&units).ok()
+            }
+            Charset::Utf32Be | Charset::Utf32Le => bytes
+                .chunks(4)
+                .map(|quad| {
+                    let quad: [u8; 4] = quad.try_into().ok()?;
+                    char::from_u32(match self {
+                        Charset::Utf32Be => u32::from_be_bytes(quad),
+                        _ => u32::from_le_bytes(quad),
+                    })
+                })
+                .collect(),
+            Charset::SingleByte(codepage) => Some(codepage.decode(bytes)),
+        }
+    }
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Charset::Utf8 => f.write_str("UTF-8"),
+            Charset::Utf16Be => f.write_str("UTF-16BE"),
+            Charset::Utf16Le => f.write_str("UTF-16LE"),
+            Charset::Utf32Be => f.write_str("UTF-32BE"),
+            Charset::Utf32Le => f.write_str("UTF-32LE"),
+            Charset::SingleByte(codepage) => fmt::Display::fmt(codepage, f),
+        }
+    }
+}
+
+/// A charset the bytes could be in, with a confidence from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub charset: Charset,
+    pub confidence: f64,
+    /// Whether the bytes start with the charset's BOM.
+    pub bom: bool,
+}
+
+impl Candidate {
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self.bom {
+            true => self.charset.decode(&bytes[self.charset.bom().len()..]),
+            false => self.charset.decode(bytes),
+        }
+    }
+}
+
+/// Every plausible charset for some bytes, most confident first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub candidates: Vec<Candidate>,
+}
+
+impl Detection {
+    pub fn best(&self) -> Option<&Candidate> {
+        self.candidates.first()
+    }
+
+    /// The bytes decoded with the most confident candidate that accepts them.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        self.candidates
+            .iter()
+            .find_map(|candidate| candidate.decode(bytes))
+    }
+}
+
+/// Guesses the charset of `bytes`. A BOM settles it outright; otherwise
+/// UTF-16 is recognised by where its null bytes fall, UTF-8 by being valid,
+/// and single-byte codepages are scored against the letters of their script.
+pub fn detect_encoding(bytes: &[u8]) -> Detection {
+    if let Some(charset) = Charset::WITH_BOM.into_iter().find(|charset| {
+        bytes.starts_with(charset.bom()) && charset.decode(&bytes[charset.bom().len()..]).is_some()
+    }) {
+        return Detection {
+            candidates: vec![Candidate {
+                charset,
+                confidence: 1.0,
+                bom: true,
+            }],
+        };
+    }
+
+    let mut candidates = Vec::new();
+    let mut candidate = |charset, confidence| {
+        candidates.push(Candidate {
+            charset,
+            confidence,
+            bom: false,
+        })
+    };
+
+    // Text in a single alphabet keeps the high byte of its UTF-16 code units
+    // nearly constant while the low byte varies: zero for ASCII and Latin-1,
+    // 0x03 for Greek, 0x04 for Cyrillic. Nulls are the strongest sign, since
+    // other text rarely has any. UTF-16 that decodes stays a candidate even
+    // without either sign.
+    if !bytes.is_empty() && bytes.len().is_multiple_of(2) {
+        let units = bytes.len() / 2;
+        let at = |parity| (0..units).map(move |i| bytes[2 * i + parity]);
+        let nulls = |parity| at(parity).filter(|byte| *byte == 0).count();
+        let most_common = |parity| {
+            let mut counts = [0usize; 256];
+            at(parity).for_each(|byte| counts[byte as usize] += 1);
+            counts.into_iter().max().unwrap_or(0)
+        };
+
+        for (charset, high, low) in [(Charset::Utf16Be, 0, 1), (Charset::Utf16Le, 1, 0)] {
+            if charset.decode(bytes).is_some() {
+                let constancy = (nulls(high).saturating_sub(nulls(low)))
+                    .max(most_common(high).saturating_sub(most_common(low)));
+                candidate(charset, 0.95 * constancy as f64 / units as f64);
+            }
+        }
+    }
+
+    // Valid UTF-8 with multibyte sequences rarely happens by accident, but
+    // null bytes almost never belong in text.
+    if std::str::from_utf8(bytes).is_ok() {
+        candidate(Charset::Utf8, if bytes.contains(&0) { 0.1 } else { 0.99 });
+    }
+
+    for codepage in Codepage::ALL {
+        let text = codepage.decode(bytes);
+        candidate(
+            Charset::SingleByte(codepage),
+            0.9 * plausibility(&text, letters(codepage)),
+        );
+    }
+
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    Detection { candidates }
+}
+
+/// The most frequent non-ASCII lowercase letters of the languages each
+/// codepage is used for, most frequent first.
+fn letters(codepage: Codepage) -> &'static str {
+    match codepage {
+        Codepage::Latin1
+        | Codepage::Windows1252
+        | Codepage::Iso8859_15
+        | Codepage::MacRoman
+        | Codepage::Cp437
+        | Codepage::Cp850 => "éáèàóíüöäñçúêãõâôëïøåæœßìòùûîÿ",
+        Codepage::Iso8859_2 | Codepage::Cp852 => "áéíóúýčřšžěůłąęśćńźżőűöüäôĺľŕ",
+        Codepage::Koi8R | Codepage::Cp866 => "оеаинтсвлркдмупяьыгзбчйхжшюцщэфъё",
+    }
+}
+
+/// Non-ASCII symbols that turn up in ordinary text.
+const SYMBOLS: &str = "€£¥°§©®";
+
+/// How much the non-ASCII characters of `text` look like the letters of a
+/// language, from 0 to 1. Other symbols, controls and capitals in the middle
+/// of a lowercase word count against it; text without evidence either way
+/// scores one half.
+fn plausibility(text: &str, letters: &str) -> f64 {
+    let letter_count = letters.chars().count() as f64;
+    let mut score = 0.0;
+    let mut count = 0;
+    let mut previous: Option<char> = None;
+
+    for c in text.chars() {
+        if !c.is_ascii() {
+            count += 1;
+
+            let lowercase = c.to_lowercase().next().unwrap_or(c);
+            score += match letters.chars().position(|letter| letter == lowercase) {
+                Some(rank) => 1.0 - 0.5 * rank as f64 / letter_count,
+                None if c.is_alphabetic() => 0.2,
+                None if PUNCTUATION.contains(c) || SYMBOLS.contains(c) => 0.5,
+                None => -1.0,
+            };
+
+            if c.is_uppercase() && previous.is_some_and(char::is_lowercase) {
+                score -= 1.0;
+            }
+        } else if c.is_ascii_control() && !c.is_ascii_whitespace() {
+            count += 1;
+            score -= 1.0;
+        }
+
+        previous = Some(c);
+    }
+
+    match count {
+        0 => 0.5,
+        _ => (score / count as f64).clamp(0.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(text: &str, charset: Charset) -> Vec<u8> {
+        match charset {
+            Charset::Utf8 => text.as_bytes().to_vec(),
+            Charset::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            Charset::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Charset::Utf32Be => text
+                .chars()
+                .flat_map(|c| (c as u32).to_be_bytes())
+                .collect(),
+            Charset::Utf32Le => text
+                .chars()
+                .flat_map(|c| (c as u32).to_le_bytes())
+                .collect(),
+            Charset::SingleByte(codepage) => codepage.encode(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn byte_order_marks() {
+        for charset in Charset::WITH_BOM {
+            let mut bytes = charset.bom().to_vec();
+            bytes.extend(encode("Größe 😀", charset));
+
+            let detection = detect_encoding(&bytes);
+            assert_eq!(
+                detection.candidates,
+                [Candidate {
+                    charset,
+                    confidence: 1.0,
+                    bom: true,
+                }],
+                "{}",
+                charset
+            );
+            assert_eq!(detection.decode(&bytes).unwrap(), "Größe 😀");
+        }
+    }
+
+    #[test]
+    fn utf16_without_a_bom() {
+        for (text, charset) in [
+            ("Größe", Charset::Utf16Le),
+            ("Größe", Charset::Utf16Be),
+            ("привет мир", Charset::Utf16Le),
+            ("привет мир", Charset::Utf16Be),
+        ] {
+            let bytes = encode(text, charset);
+            let detection = detect_encoding(&bytes);
+
+            assert_eq!(detection.best().unwrap().charset, charset, "{}", text);
+            assert!(!detection.best().unwrap().bom);
+            assert_eq!(detection.decode(&bytes).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn utf8_without_a_bom() {
+        let detection = detect_encoding("Größe".as_bytes());
+        assert_eq!(detection.best().unwrap().charset, Charset::Utf8);
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_a_codepage() {
+        for (text, codepage) in [
+            ("Größe café", Codepage::Latin1),
+            ("Žluťoučký kůň", Codepage::Iso8859_2),
+            ("привет мир", Codepage::Koi8R),
+        ] {
+            let bytes = encode(text, Charset::SingleByte(codepage));
+            assert!(std::str::from_utf8(&bytes).is_err());
+
+            let detection = detect_encoding(&bytes);
+            assert!(
+                !detection
+                    .candidates
+                    .iter()
+                    .any(|candidate| candidate.charset == Charset::Utf8),
+                "{}",
+                text
+            );
+            assert_eq!(detection.decode(&bytes).unwrap(), text, "{}", text);
+        }
+    }
+}
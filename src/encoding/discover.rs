@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+
+use crate::script::{plausibility, PUNCTUATION};
+
+use super::{
+    detect::{detect_encoding, Charset},
+    pipeline::{Data, Pipeline, Transform},
+};
+
+/// The layers of an encoded blob found by [`discover`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discovery {
+    pub pipeline: Pipeline,
+    /// The input followed by the output of each stage.
+    pub stages: Vec<Data>,
+    pub score: f64,
+}
+
+/// Unbroken runs this long of letters and digits are a sign of Base64 or
+/// hex rather than of words.
+const ENCODED_RUN: usize = 24;
+
+/// Text is expected to have a space or punctuation mark at least this often,
+/// so a single word this long needs none.
+const WORD_LENGTH: f64 = 8.0;
+
+/// ASCII punctuation that separates words or sentences.
+const SEPARATORS: &str = ",.;:!?'\"()-";
+
+/// How much `text` looks like the end of the search: plausible text in the
+/// sense of [`plausibility`], further discounted when it lacks the spaces or
+/// punctuation that separate words, is mostly capitals or has capitals in the
+/// middle of words as mojibake tends to, or looks like another layer of
+/// Base64 or hex. Control characters other than line breaks and tabs rule it
+/// out, except for the nulls that padding leaves at the end.
+fn score(text: &str) -> f64 {
+    let text = text.trim_end_matches('\0');
+
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+    {
+        return 0.0;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let length = chars.len() as f64;
+
+    let separators = chars
+        .iter()
+        .filter(|&&c| c.is_whitespace() || SEPARATORS.contains(c) || PUNCTUATION.contains(c))
+        .count() as f64;
+
+    let letters = chars.iter().filter(|c| c.is_alphabetic()).count().max(1) as f64;
+    let capitals = chars.iter().filter(|c| c.is_uppercase()).count() as f64 / letters;
+    let mid_word_capitals = chars
+        .windows(2)
+        .filter(|pair| pair[0].is_lowercase() && pair[1].is_uppercase())
+        .count() as f64
+        / letters;
+
+    let longest_run = text
+        .split(|c: char| !(c.is_ascii_alphanumeric() || "+/=".contains(c)))
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+
+    let mut score = plausibility(text).score;
+    score *= 0.5 + 0.5 * ((separators + 1.0) * WORD_LENGTH / length).min(1.0);
+    score *= 1.0 - 0.5 * (capitals - 0.5).max(0.0);
+    score *= 1.0 - 0.5 * (4.0 * mid_word_capitals).min(1.0);
+
+    match longest_run >= ENCODED_RUN {
+        true => score / 2.0,
+        false => score,
+    }
+}
+
+/// The transforms worth trying on `data`. Bytes may be Base64, hex or any
+/// Unicode encoding, legacy UTF-8 included, or else the most likely
+/// single-byte codepage. Text may be Base64 or hex too, and text or values
+/// may be units of a few bits more than their largest value needs.
+fn candidates(data: &Data) -> Vec<Transform> {
+    let mut transforms = vec![Transform::Base64, Transform::Hex];
+
+    match data {
+        Data::Bytes(bytes) => {
+            if Charset::WITH_BOM
+                .iter()
+                .any(|charset| bytes.starts_with(charset.bom()))
+            {
+                transforms.push(Transform::StripBom);
+            }
+
+            transforms.extend(
+                [
+                    Charset::Utf8,
+                    Charset::Utf16Le,
+                    Charset::Utf16Be,
+                    Charset::Utf32Le,
+                    Charset::Utf32Be,
+                ]
+                .map(Transform::Decode),
+            );
+            transforms.push(Transform::Utf8Rfc2279);
+
+            let codepage = detect_encoding(bytes)
+                .candidates
+                .into_iter()
+                .find(|candidate| matches!(candidate.charset, Charset::SingleByte(_)));
+
+            if let Some(candidate) = codepage {
+                transforms.push(Transform::Decode(candidate.charset));
+            }
+        }
+        Data::Text(_) | Data::Values(_) => {
+            let largest = data.to_values().into_iter().max().unwrap_or(0);
+            let needed = (32 - largest.leading_zeros()).max(1);
+
+            transforms.extend((needed..=needed.next_multiple_of(4).min(32)).map(Transform::Bits));
+        }
+    }
+
+    transforms
+}
+
+/// Tries every chain of transforms up to `depth` long on `input`, shortest
+/// first, and keeps the one whose text scores best. Ties go to the longer
+/// text, which is more evidence of a real message, and then to the shorter
+/// chain. Results already reached by a shorter chain are not explored
+/// again.
+pub fn discover(input: Data, depth: usize) -> Option<Discovery> {
+    let mut seen: HashSet<Data> = HashSet::from([input.clone()]);
+    let mut level: Vec<(Vec<Transform>, Data)> = vec![(Vec::new(), input.clone())];
+    let mut best: Option<(f64, usize, Vec<Transform>)> = None;
+
+    for _ in 0..depth {
+        let mut next = Vec::new();
+
+        for (transforms, data) in level {
+            for transform in candidates(&data) {
+                let Ok(output) = transform.apply(&data) else {
+                    continue;
+                };
+
+                if !seen.insert(output.clone()) {
+                    continue;
+                }
+
+                let mut chain = transforms.clone();
+                chain.push(transform);
+
+                if let Data::Text(text) = &output {
+                    let score = score(text);
+                    let length = text.trim_end_matches('\0').chars().count();
+                    let better = best.as_ref().is_none_or(|(best, best_length, _)| {
+                        score.total_cmp(best).then(length.cmp(best_length)).is_gt()
+                    });
+
+                    if length > 0 && better {
+                        best = Some((score, length, chain.clone()));
+                    }
+                }
+
+                next.push((chain, output));
+            }
+        }
+
+        level = next;
+    }
+
+    let (score, _, transforms) = best?;
+    let pipeline = Pipeline { transforms };
+    let stages = pipeline.trace(input).ok()?;
+
+    Some(Discovery {
+        pipeline,
+        stages,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYERS: &str = "base64|utf16le|bits20|utf8-2279|bits28|utf8";
+
+    fn discover_text(input: &str) -> Discovery {
+        discover(Data::Text(input.to_string()), 6).unwrap()
+    }
+
+    #[test]
+    fn rediscovers_the_day_20_chain() {
+        let discovery = discover_text("//6y22jcKNmY3pvayN/m2JbdJtpw3g==\n");
+
+        assert_eq!(discovery.pipeline, LAYERS.parse().unwrap());
+        assert_eq!(
+            discovery.stages.last(),
+            Some(&Data::Text("abcdefg".to_string()))
+        );
+    }
+
+    #[test]
+    fn rediscovers_the_day_20_chain_beyond_ascii() {
+        let discovery = discover_text("//6y20ncLNuw3q/ayN9s2bncttpf3mDasN9e2p/f4tqg3MLZCNwAAA==");
+        let text = discovery.stages.last().unwrap().to_text().unwrap();
+
+        assert_eq!(discovery.pipeline, LAYERS.parse().unwrap());
+        assert_eq!(text.trim_end_matches('\0'), "Größe 😀");
+    }
+
+    #[test]
+    fn control_characters_rule_text_out() {
+        assert_eq!(score("abc\u{1}def"), 0.0);
+        assert_eq!(score("abc\u{85}def"), 0.0);
+        assert_eq!(score("abcdefg\0\0"), score("abcdefg"));
+        assert!(score("two\nlines\tand tabs") > 0.9);
+    }
+
+    #[test]
+    fn short_words_need_no_separators() {
+        assert_eq!(score("abcdefg"), 1.0);
+        assert!(score("abcdefghijklmnopqrstuvw") < 0.75);
+    }
+}
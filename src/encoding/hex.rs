@@ -0,0 +1,153 @@
+use std::io::{Error, ErrorKind, Result};
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Decodes a hex dump: digits in either case, with or without whitespace
+/// between them, or the output of `xxd`, whose offsets and text column are
+/// skipped. Errors give the byte offset of the offending digit in `text`.
+pub fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut high: Option<(usize, u8)> = None;
+    let mut line_start = 0;
+
+    for line in text.split_inclusive('\n') {
+        let (start, digits) = hex_column(line);
+
+        for (i, c) in digits.char_indices() {
+            if c.is_whitespace() {
+                continue;
+            }
+
+            let offset = line_start + start + i;
+            let nibble = c.to_digit(16).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid hex digit {:?} at offset {}", c, offset),
+                )
+            })? as u8;
+
+            match high.take() {
+                Some((_, high)) => bytes.push(high << 4 | nibble),
+                None => high = Some((offset, nibble)),
+            }
+        }
+
+        line_start += line.len();
+    }
+
+    match high {
+        Some((offset, _)) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Odd number of hex digits, unpaired digit at offset {}",
+                offset
+            ),
+        )),
+        None => Ok(bytes),
+    }
+}
+
+/// The part of `line` holding hex digits, and where it starts. `xxd` lines
+/// start with a hex offset and a colon, and end with the bytes as text after
+/// two spaces.
+fn hex_column(line: &str) -> (usize, &str) {
+    let Some((offset, rest)) = line.split_once(':') else {
+        return (0, line);
+    };
+
+    if offset.is_empty() || !offset.chars().all(|c| c.is_ascii_hexdigit()) {
+        return (0, line);
+    }
+
+    let rest = rest.strip_prefix(' ').unwrap_or(rest);
+    let start = line.len() - rest.len();
+
+    match rest.find("  ") {
+        Some(end) => (start, &rest[..end]),
+        None => (start, rest),
+    }
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// A dump in the default format of `xxd`: sixteen bytes a line in groups of
+/// two, after the offset and before the bytes as text.
+pub fn xxd(bytes: &[u8]) -> String {
+    bytes
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(line, chunk)| {
+            let groups = chunk
+                .chunks(2)
+                .map(encode_hex)
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            let text: String = chunk
+                .iter()
+                .map(|&byte| match byte {
+                    0x20..=0x7E => byte as char,
+                    _ => '.',
+                })
+                .collect();
+
+            format!(
+                "{:08x}: {:<width$}  {}\n",
+                line * BYTES_PER_LINE,
+                groups,
+                text,
+                width = BYTES_PER_LINE * 5 / 2 - 1
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYTES: &[u8] = b"Hello, world! \x00\xff\x10 two  spaces:\n";
+
+    /// `printf 'Hello, world! \x00\xff\x10 two  spaces:\n' | xxd`
+    const XXD: &str = "\
+00000000: 4865 6c6c 6f2c 2077 6f72 6c64 2120 00ff  Hello, world! ..
+00000010: 1020 7477 6f20 2073 7061 6365 733a 0a    . two  spaces:.
+";
+
+    #[test]
+    fn plain_digits() {
+        assert_eq!(decode_hex("48656c6C6F").unwrap(), b"Hello");
+        assert_eq!(decode_hex("48 65\n6c 6c\t6f\n").unwrap(), b"Hello");
+        assert_eq!(decode_hex("").unwrap(), b"");
+    }
+
+    #[test]
+    fn xxd_output() {
+        assert_eq!(decode_hex(XXD).unwrap(), BYTES);
+        assert_eq!(
+            decode_hex("00000000: 6162 63                                  abc").unwrap(),
+            b"abc"
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        assert_eq!(xxd(BYTES), XXD);
+        assert_eq!(decode_hex(&xxd(BYTES)).unwrap(), BYTES);
+        assert_eq!(decode_hex(&encode_hex(BYTES)).unwrap(), BYTES);
+    }
+
+    #[test]
+    fn errors_give_the_offset() {
+        let error = decode_hex("4865\n6g").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid hex digit 'g' at offset 6");
+
+        let error = decode_hex("00000000: 4865 6").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Odd number of hex digits, unpaired digit at offset 15"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+pub mod codepage;
+pub mod detect;
+pub mod discover;
+mod dos;
+pub mod hex;
+pub mod mojibake;
+pub mod pipeline;
+pub mod rfc2279;
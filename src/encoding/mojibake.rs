@@ -0,0 +1,129 @@
+use super::codepage::Codepage;
+
+/// Text with its mojibake undone, how many layers of misreading that took,
+/// and the codepage the UTF-8 was misread as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub text: String,
+    pub layers: usize,
+    pub codepage: Option<Codepage>,
+}
+
+/// Undoes UTF-8 misread as whichever codepage removes the most layers,
+/// preferring the earlier of [`Codepage::ALL`] on ties. Clean text, including
+/// plain ASCII, comes back unchanged with no layers.
+pub fn fix_mojibake(text: &str) -> Repair {
+    Codepage::ALL
+        .iter()
+        .map(|&codepage| fix_mojibake_as(text, codepage))
+        .rev()
+        .max_by_key(|repair| repair.layers)
+        .unwrap()
+}
+
+/// Repeatedly undoes UTF-8 that was misread as `codepage`, for as long as the
+/// whole text still reads as the result of such a misreading.
+pub fn fix_mojibake_as(text: &str, codepage: Codepage) -> Repair {
+    let mut repair = Repair {
+        text: text.to_string(),
+        layers: 0,
+        codepage: None,
+    };
+
+    while let Some(text) = undo_layer(&repair.text, codepage) {
+        repair.text = text;
+        repair.layers += 1;
+        repair.codepage = Some(codepage);
+    }
+
+    repair
+}
+
+/// Maps every character back to the byte it was misread from and decodes the
+/// bytes as UTF-8, failing if either step does. ASCII is its own encoding in
+/// every layer, so it never counts as one.
+fn undo_layer(text: &str, codepage: Codepage) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+
+    String::from_utf8(codepage.encode(text)?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `text` encoded as UTF-8 and misread as `codepage`, `layers` times.
+    fn misread(text: &str, codepage: Codepage, layers: usize) -> String {
+        (0..layers).fold(text.to_string(), |text, _| codepage.decode(text.as_bytes()))
+    }
+
+    #[test]
+    fn two_layers_of_latin1() {
+        let mangled = misread("Größe café", Codepage::Latin1, 2);
+        assert_eq!(mangled, "GrÃ\u{83}Â¶Ã\u{83}Â\u{9f}e cafÃ\u{83}Â©");
+
+        assert_eq!(
+            fix_mojibake(&mangled),
+            Repair {
+                text: "Größe café".to_string(),
+                layers: 2,
+                codepage: Some(Codepage::Latin1),
+            }
+        );
+    }
+
+    #[test]
+    fn clean_text_is_unchanged() {
+        for text in ["plain ASCII", "Größe café naïve", "привет", "日本語", ""] {
+            assert_eq!(
+                fix_mojibake(text),
+                Repair {
+                    text: text.to_string(),
+                    layers: 0,
+                    codepage: None,
+                },
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn windows_1252_punctuation_is_not_repaired() {
+        // Each of these is in 0x80–0x9F in Windows-1252, which is never a
+        // UTF-8 lead byte.
+        for text in ["Price: €5 – “ok”", "Œuvre ‰ … ™", "‘quoted’ • Ÿ"] {
+            let repair = fix_mojibake(text);
+            assert_eq!(
+                (repair.text.as_str(), repair.layers),
+                (text, 0),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_through_every_codepage() {
+        for codepage in Codepage::ALL.into_iter().chain(Codepage::DOS) {
+            for text in ["Größe café", "Łódź", "привет"] {
+                let mangled = misread(text, codepage, 1);
+                assert_ne!(mangled, text);
+
+                assert_eq!(
+                    fix_mojibake_as(&mangled, codepage),
+                    Repair {
+                        text: text.to_string(),
+                        layers: 1,
+                        codepage: Some(codepage),
+                    },
+                    "{} {:?}",
+                    codepage.name(),
+                    text
+                );
+            }
+        }
+    }
+}
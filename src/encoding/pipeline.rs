@@ -0,0 +1,353 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use base64::prelude::*;
+
+use super::{
+    codepage::Codepage,
+    detect::Charset,
+    hex::{decode_hex, xxd},
+    rfc2279::{self, Mode},
+};
+
+/// What flows between the stages of a [`Pipeline`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Data {
+    Bytes(Vec<u8>),
+    Text(String),
+    /// Numbers of up to 32 bits, such as the values of legacy UTF-8, which
+    /// may lie beyond Unicode.
+    Values(Vec<u32>),
+}
+
+impl Data {
+    /// The data as bytes: text as UTF-8, and values as one byte each if they
+    /// all fit.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Data::Bytes(bytes) => Ok(bytes.clone()),
+            Data::Text(text) => Ok(text.clone().into_bytes()),
+            Data::Values(values) => values
+                .iter()
+                .map(|&value| u8::try_from(value))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| invalid("Values above 0xff are not bytes".to_string())),
+        }
+    }
+
+    /// The data as numbers: text as its code points and bytes one by one.
+    pub fn to_values(&self) -> Vec<u32> {
+        match self {
+            Data::Bytes(bytes) => bytes.iter().map(|&byte| byte as u32).collect(),
+            Data::Text(text) => text.chars().map(|c| c as u32).collect(),
+            Data::Values(values) => values.clone(),
+        }
+    }
+
+    /// The data as text, decoding bytes as UTF-8.
+    pub fn to_text(&self) -> Result<String> {
+        match self {
+            Data::Text(text) => Ok(text.clone()),
+            data => String::from_utf8(data.to_bytes()?)
+                .map_err(|_| invalid("The bytes are not UTF-8".to_string())),
+        }
+    }
+}
+
+/// Text as it is, bytes as a hex dump and values in hex.
+impl fmt::Display for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Data::Text(text) => f.write_str(text),
+            Data::Bytes(bytes) => f.write_str(xxd(bytes).trim_end()),
+            Data::Values(values) => {
+                let values: Vec<String> =
+                    values.iter().map(|value| format!("{:x}", value)).collect();
+                f.write_str(&values.join(" "))
+            }
+        }
+    }
+}
+
+/// One stage of a [`Pipeline`], named as on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    /// `base64`: standard Base64 text, whitespace ignored, to bytes.
+    Base64,
+    /// `hex`: a hex dump to bytes.
+    Hex,
+    /// `bom`: bytes without the byte order mark they start with, if any.
+    StripBom,
+    /// `utf8`, `utf16le` and so on: bytes to text, dropping a BOM.
+    Decode(Charset),
+    /// `utf8-2279`: bytes to the values of RFC 2279 UTF-8, overlong forms
+    /// and all.
+    Utf8Rfc2279,
+    /// `bitsN`: values as `N`-bit units one after another, read back as
+    /// bytes. Bits left over at the end are padding and dropped.
+    Bits(u32),
+}
+
+impl Transform {
+    pub fn apply(&self, data: &Data) -> Result<Data> {
+        match self {
+            Transform::Base64 => {
+                let text: String = data.to_text()?.split_whitespace().collect();
+                BASE64_STANDARD
+                    .decode(text)
+                    .map(Data::Bytes)
+                    .map_err(|e| invalid(e.to_string()))
+            }
+            Transform::Hex => decode_hex(&data.to_text()?).map(Data::Bytes),
+            Transform::StripBom => {
+                let bytes = data.to_bytes()?;
+                let bom = Charset::WITH_BOM
+                    .iter()
+                    .map(Charset::bom)
+                    .find(|bom| bytes.starts_with(bom))
+                    .unwrap_or_default();
+
+                Ok(Data::Bytes(bytes[bom.len()..].to_vec()))
+            }
+            Transform::Decode(charset) => {
+                let bytes = data.to_bytes()?;
+                let bytes = bytes.strip_prefix(charset.bom()).unwrap_or(&bytes);
+
+                charset
+                    .decode(bytes)
+                    .map(Data::Text)
+                    .ok_or_else(|| invalid(format!("The bytes are not {}", charset)))
+            }
+            Transform::Utf8Rfc2279 => {
+                rfc2279::decode(&data.to_bytes()?, Mode::Lenient).map(Data::Values)
+            }
+            Transform::Bits(width) => {
+                let mut bytes = Vec::new();
+                let mut buffer: u64 = 0;
+                let mut bits = 0;
+
+                for value in data.to_values() {
+                    if *width < 32 && value >> width != 0 {
+                        return Err(invalid(format!(
+                            "{:#x} does not fit in {} bits",
+                            value, width
+                        )));
+                    }
+
+                    buffer = buffer << width | value as u64;
+                    bits += width;
+
+                    while bits >= 8 {
+                        bits -= 8;
+                        bytes.push((buffer >> bits) as u8);
+                    }
+
+                    buffer &= (1 << bits) - 1;
+                }
+
+                Ok(Data::Bytes(bytes))
+            }
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = Error;
+
+    fn from_str(label: &str) -> Result<Self> {
+        let label = label.trim().to_ascii_lowercase();
+
+        if let Some(width) = label.strip_prefix("bits") {
+            return match width.parse() {
+                Ok(width @ 1..=32) => Ok(Transform::Bits(width)),
+                _ => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unit width must be 1 to 32 bits, not {:?}", width),
+                )),
+            };
+        }
+
+        match label.as_str() {
+            "base64" => Ok(Transform::Base64),
+            "hex" => Ok(Transform::Hex),
+            "bom" => Ok(Transform::StripBom),
+            "utf8" | "utf-8" => Ok(Transform::Decode(Charset::Utf8)),
+            "utf16be" | "utf-16be" => Ok(Transform::Decode(Charset::Utf16Be)),
+            "utf16le" | "utf-16le" => Ok(Transform::Decode(Charset::Utf16Le)),
+            "utf32be" | "utf-32be" => Ok(Transform::Decode(Charset::Utf32Be)),
+            "utf32le" | "utf-32le" => Ok(Transform::Decode(Charset::Utf32Le)),
+            "utf8-2279" | "rfc2279" => Ok(Transform::Utf8Rfc2279),
+            label => match label.parse::<Codepage>() {
+                Ok(codepage) => Ok(Transform::Decode(Charset::SingleByte(codepage))),
+                Err(_) => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown transform {:?}", label),
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transform::Base64 => f.write_str("base64"),
+            Transform::Hex => f.write_str("hex"),
+            Transform::StripBom => f.write_str("bom"),
+            Transform::Decode(Charset::Utf8) => f.write_str("utf8"),
+            Transform::Decode(Charset::Utf16Be) => f.write_str("utf16be"),
+            Transform::Decode(Charset::Utf16Le) => f.write_str("utf16le"),
+            Transform::Decode(Charset::Utf32Be) => f.write_str("utf32be"),
+            Transform::Decode(Charset::Utf32Le) => f.write_str("utf32le"),
+            Transform::Decode(Charset::SingleByte(codepage)) => {
+                f.write_str(&codepage.name().to_ascii_lowercase())
+            }
+            Transform::Utf8Rfc2279 => f.write_str("utf8-2279"),
+            Transform::Bits(width) => write!(f, "bits{}", width),
+        }
+    }
+}
+
+/// Transforms applied one after another, written like
+/// `base64|utf16le|bits20|utf8-2279|bits28|utf8`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    pub transforms: Vec<Transform>,
+}
+
+impl Pipeline {
+    pub fn apply(&self, data: Data) -> Result<Data> {
+        Ok(self.trace(data)?.pop().unwrap_or(Data::Bytes(Vec::new())))
+    }
+
+    /// The input followed by the output of each stage. Errors name the stage
+    /// that failed.
+    pub fn trace(&self, data: Data) -> Result<Vec<Data>> {
+        let mut stages = vec![data];
+
+        for (i, transform) in self.transforms.iter().enumerate() {
+            let next = transform.apply(&stages[i]).map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    format!("Stage {} ({}) failed: {}", i + 1, transform, e),
+                )
+            })?;
+            stages.push(next);
+        }
+
+        Ok(stages)
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = Error;
+
+    fn from_str(description: &str) -> Result<Self> {
+        let transforms = description
+            .split('|')
+            .filter(|label| !label.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_>>()?;
+
+        Ok(Pipeline { transforms })
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = self.transforms.iter().map(Transform::to_string).collect();
+        f.write_str(&labels.join("|"))
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The layers of the day 20 message.
+    const LAYERS: &str = "base64|utf16le|bits20|utf8-2279|bits28|utf8";
+
+    fn pipeline() -> Pipeline {
+        LAYERS.parse().unwrap()
+    }
+
+    #[test]
+    fn day_20_chain() {
+        let data = Data::Text("//6y22jcKNmY3pvayN/m2JbdJtpw3g==\n".to_string());
+
+        assert_eq!(
+            pipeline().apply(data).unwrap(),
+            Data::Text("abcdefg".to_string())
+        );
+    }
+
+    #[test]
+    fn day_20_chain_beyond_ascii() {
+        // Padded with zero bits up to whole 28- and 20-bit units, which come
+        // out as nulls.
+        let data =
+            Data::Text("//6y20ncLNuw3q/ayN9s2bncttpf3mDasN9e2p/f4tqg3MLZCNwAAA==".to_string());
+        let text = pipeline().apply(data).unwrap().to_text().unwrap();
+
+        assert_eq!(text.trim_end_matches('\0'), "Größe 😀");
+    }
+
+    #[test]
+    fn trace_keeps_every_stage() {
+        let stages = pipeline()
+            .trace(Data::Text("//6y22jcKNmY3pvayN/m2JbdJtpw3g==".to_string()))
+            .unwrap();
+
+        assert_eq!(stages.len(), 7);
+        assert!(matches!(&stages[1], Data::Bytes(bytes) if bytes.starts_with(&[0xFF, 0xFE])));
+        assert!(matches!(stages[2], Data::Text(_)));
+        assert_eq!(stages[4], Data::Values(vec![0x6162636, 0x4656667]));
+        assert_eq!(stages[5].to_bytes().unwrap(), b"abcdefg");
+    }
+
+    #[test]
+    fn labels_round_trip() {
+        assert_eq!(pipeline().to_string(), LAYERS);
+        assert_eq!(
+            "Base64 | HEX | bom | utf-8 | cp437 |"
+                .parse::<Pipeline>()
+                .unwrap()
+                .to_string(),
+            "base64|hex|bom|utf8|cp437"
+        );
+        assert!("bits0".parse::<Pipeline>().is_err());
+        assert!("bits33".parse::<Pipeline>().is_err());
+        assert!("base64|rot13".parse::<Pipeline>().is_err());
+    }
+
+    #[test]
+    fn bits_pack_values_and_drop_padding() {
+        let data = Data::Values(vec![0xABC, 0xDEF, 0x1]);
+
+        assert_eq!(
+            Transform::Bits(12).apply(&data).unwrap(),
+            Data::Bytes(vec![0xAB, 0xCD, 0xEF, 0x00])
+        );
+        assert!(Transform::Bits(8).apply(&data).is_err());
+    }
+
+    #[test]
+    fn errors_name_the_stage() {
+        let error = pipeline()
+            .apply(Data::Text("not base64!".to_string()))
+            .unwrap_err();
+
+        assert!(
+            error.to_string().starts_with("Stage 1 (base64) failed:"),
+            "{}",
+            error
+        );
+    }
+}
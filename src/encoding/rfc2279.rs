@@ -0,0 +1,279 @@
+use std::io::{Error, ErrorKind, Result};
+
+/// The largest value RFC 2279 can encode, in six bytes.
+pub const MAX_VALUE: u32 = 0x7FFF_FFFF;
+
+/// The smallest value that needs each length of sequence, from one byte up.
+const MIN_VALUES: [u32; 6] = [0, 0x80, 0x800, 0x1_0000, 0x20_0000, 0x400_0000];
+
+/// What the decoder accepts besides well-formed shortest sequences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Overlong forms and UTF-16 surrogates are errors.
+    #[default]
+    Strict,
+    /// Overlong forms and surrogates decode to their values.
+    Lenient,
+}
+
+/// The length of the sequence `lead` starts, or `None` for a continuation
+/// byte or one of the two bytes that never appear.
+fn sequence_length(lead: u8) -> Option<usize> {
+    match lead.leading_ones() {
+        0 => Some(1),
+        ones @ 2..=6 => Some(ones as usize),
+        _ => None,
+    }
+}
+
+/// Decodes the original, pre-RFC 3629 form of UTF-8, whose sequences run to
+/// six bytes and 31 bits, from bytes that may arrive a piece at a time.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    mode: Mode,
+    /// How many bytes have been fed so far.
+    offset: usize,
+    /// Where the sequence being decoded started.
+    start: usize,
+    value: u32,
+    length: usize,
+    remaining: usize,
+}
+
+impl Decoder {
+    pub fn new(mode: Mode) -> Self {
+        Decoder {
+            mode,
+            ..Decoder::default()
+        }
+    }
+
+    /// Decodes as many values as `bytes` completes, keeping an unfinished
+    /// sequence for the next call. Errors give the offset of the sequence
+    /// counting every byte fed.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<u32>> {
+        let mut values = Vec::new();
+
+        for &byte in bytes {
+            let offset = self.offset;
+            self.offset += 1;
+
+            if self.remaining == 0 {
+                self.start = offset;
+                self.length = sequence_length(byte).ok_or_else(|| {
+                    invalid(format!(
+                        "Invalid lead byte {:#04x} at offset {}",
+                        byte, offset
+                    ))
+                })?;
+                self.remaining = self.length - 1;
+                self.value = match self.length {
+                    1 => byte as u32,
+                    length => (byte & 0xFF >> (length + 1)) as u32,
+                };
+            } else if byte & 0b1100_0000 == 0b1000_0000 {
+                self.value = self.value << 6 | (byte & 0b0011_1111) as u32;
+                self.remaining -= 1;
+            } else {
+                return Err(invalid(format!(
+                    "Sequence at offset {} cut short by {:#04x} at offset {}",
+                    self.start, byte, offset
+                )));
+            }
+
+            if self.remaining == 0 {
+                values.push(self.check()?);
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Fails if the bytes fed so far end in the middle of a sequence.
+    pub fn finish(self) -> Result<()> {
+        match self.remaining {
+            0 => Ok(()),
+            _ => Err(invalid(format!(
+                "Truncated sequence at offset {}",
+                self.start
+            ))),
+        }
+    }
+
+    fn check(&self) -> Result<u32> {
+        if self.mode == Mode::Strict {
+            if self.value < MIN_VALUES[self.length - 1] {
+                return Err(invalid(format!(
+                    "Overlong form of {:#x} at offset {}",
+                    self.value, self.start
+                )));
+            }
+
+            if (0xD800..=0xDFFF).contains(&self.value) {
+                return Err(invalid(format!(
+                    "Surrogate U+{:04X} at offset {}",
+                    self.value, self.start
+                )));
+            }
+        }
+
+        Ok(self.value)
+    }
+}
+
+/// Decodes all of `bytes` as RFC 2279 UTF-8 into the values it encodes, which
+/// may be beyond the range of Unicode.
+pub fn decode(bytes: &[u8], mode: Mode) -> Result<Vec<u32>> {
+    let mut decoder = Decoder::new(mode);
+    let values = decoder.feed(bytes)?;
+    decoder.finish()?;
+
+    Ok(values)
+}
+
+/// Appends the shortest sequence for `value` to `bytes`.
+pub fn encode_value(value: u32, bytes: &mut Vec<u8>) -> Result<()> {
+    if value > MAX_VALUE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{:#x} is too large for RFC 2279", value),
+        ));
+    }
+
+    let length = MIN_VALUES
+        .iter()
+        .rposition(|&min| value >= min)
+        .unwrap_or(0)
+        + 1;
+
+    if length == 1 {
+        bytes.push(value as u8);
+        return Ok(());
+    }
+
+    let lead = !(0xFFu8 >> length) | (value >> (6 * (length - 1))) as u8;
+    bytes.push(lead);
+    bytes.extend(
+        (0..length - 1)
+            .rev()
+            .map(|i| 0x80 | (value >> (6 * i)) as u8 & 0x3F),
+    );
+
+    Ok(())
+}
+
+pub fn encode(values: &[u32]) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for &value in values {
+        encode_value(value, &mut bytes)?;
+    }
+
+    Ok(bytes)
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first and last value of each length of sequence, and the length.
+    const BOUNDARIES: [(u32, u32, usize); 6] = [
+        (0, 0x7F, 1),
+        (0x80, 0x7FF, 2),
+        (0x800, 0xFFFF, 3),
+        (0x1_0000, 0x1F_FFFF, 4),
+        (0x20_0000, 0x3FF_FFFF, 5),
+        (0x400_0000, MAX_VALUE, 6),
+    ];
+
+    #[test]
+    fn round_trips_at_every_length() {
+        for (first, last, length) in BOUNDARIES {
+            for value in [first, last] {
+                let bytes = encode(&[value]).unwrap();
+                assert_eq!(bytes.len(), length, "{:#x}", value);
+
+                for mode in [Mode::Strict, Mode::Lenient] {
+                    assert_eq!(decode(&bytes, mode).unwrap(), vec![value]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn agrees_with_utf8() {
+        let text = "aé€😀";
+        let values: Vec<u32> = text.chars().map(u32::from).collect();
+
+        assert_eq!(encode(&values).unwrap(), text.as_bytes());
+        assert_eq!(decode(text.as_bytes(), Mode::Strict).unwrap(), values);
+    }
+
+    #[test]
+    fn six_byte_sequences() {
+        assert_eq!(
+            encode(&[MAX_VALUE]).unwrap(),
+            [0xFD, 0xBF, 0xBF, 0xBF, 0xBF, 0xBF]
+        );
+        assert!(encode(&[MAX_VALUE + 1]).is_err());
+    }
+
+    #[test]
+    fn overlong_forms_only_in_lenient_mode() {
+        // The largest value of each length, one byte longer than it needs.
+        for (_, last, length) in &BOUNDARIES[..5] {
+            let length = length + 1;
+            let mut bytes = vec![!(0xFFu8 >> length) | (last >> (6 * (length - 1))) as u8];
+            bytes.extend(
+                (0..length - 1)
+                    .rev()
+                    .map(|i| 0x80 | (last >> (6 * i)) as u8 & 0x3F),
+            );
+
+            assert!(decode(&bytes, Mode::Strict).is_err(), "{:x?}", bytes);
+            assert_eq!(decode(&bytes, Mode::Lenient).unwrap(), vec![*last]);
+        }
+    }
+
+    #[test]
+    fn surrogates_only_in_lenient_mode() {
+        let bytes = encode(&[0xD800, 0xDFFF]).unwrap();
+
+        assert_eq!(
+            decode(&bytes, Mode::Strict).unwrap_err().to_string(),
+            "Surrogate U+D800 at offset 0"
+        );
+        assert_eq!(decode(&bytes, Mode::Lenient).unwrap(), vec![0xD800, 0xDFFF]);
+    }
+
+    #[test]
+    fn malformed_sequences() {
+        let error = |bytes: &[u8]| decode(bytes, Mode::Lenient).unwrap_err().to_string();
+
+        assert_eq!(error(&[0x80]), "Invalid lead byte 0x80 at offset 0");
+        assert_eq!(error(b"a\xFE"), "Invalid lead byte 0xfe at offset 1");
+        assert_eq!(
+            error(b"a\xE2\x82a"),
+            "Sequence at offset 1 cut short by 0x61 at offset 3"
+        );
+        assert_eq!(error(b"ab\xF0\x9F"), "Truncated sequence at offset 2");
+    }
+
+    #[test]
+    fn sequences_split_across_feeds() {
+        let bytes = encode(&[0x41, MAX_VALUE, 0x20AC]).unwrap();
+        let mut decoder = Decoder::new(Mode::Strict);
+
+        let values: Vec<u32> = bytes
+            .chunks(4)
+            .flat_map(|chunk| decoder.feed(chunk).unwrap())
+            .collect();
+        decoder.finish().unwrap();
+
+        assert_eq!(values, vec![0x41, MAX_VALUE, 0x20AC]);
+    }
+}
@@ -0,0 +1,275 @@
+use std::ops::{Index, IndexMut};
+
+/// A cell of a grid as its row and column.
+pub type Position = (usize, usize);
+
+/// One side of a cell, in clockwise order from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Up, Side::Right, Side::Down, Side::Left];
+
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::Up => Side::Down,
+            Side::Right => Side::Left,
+            Side::Down => Side::Up,
+            Side::Left => Side::Right,
+        }
+    }
+}
+
+/// A rectangle of cells stored row by row, where every access outside it
+/// gives `None` rather than wrapping around or underflowing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    rows: usize,
+    cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(rows: usize, cols: usize, mut f: impl FnMut(Position) -> T) -> Self {
+        let cells = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .map(&mut f)
+            .collect();
+
+        Grid { rows, cols, cells }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.0 < self.rows && position.1 < self.cols
+    }
+
+    /// Where `position` is stored, if it is in the grid.
+    pub fn index_of(&self, position: Position) -> Option<usize> {
+        match self.contains(position) {
+            true => Some(position.0 * self.cols + position.1),
+            false => None,
+        }
+    }
+
+    pub fn position_of(&self, index: usize) -> Position {
+        (index / self.cols, index % self.cols)
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        self.index_of(position).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        self.index_of(position).map(|i| &mut self.cells[i])
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.rows).map(|row| self.row(row))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// The first position, in reading order, whose cell satisfies `predicate`.
+    pub fn position(&self, predicate: impl FnMut(&T) -> bool) -> Option<Position> {
+        self.cells
+            .iter()
+            .position(predicate)
+            .map(|i| self.position_of(i))
+    }
+
+    /// The cell across `side` of `position`, or `None` at the edge.
+    pub fn neighbour(&self, position: Position, side: Side) -> Option<Position> {
+        let (row, col) = position;
+
+        let neighbour = match side {
+            Side::Up => (row.checked_sub(1)?, col),
+            Side::Right => (row, col + 1),
+            Side::Down => (row + 1, col),
+            Side::Left => (row, col.checked_sub(1)?),
+        };
+
+        match self.contains(neighbour) {
+            true => Some(neighbour),
+            false => None,
+        }
+    }
+
+    /// The cells across each side of `position` that are in the grid.
+    pub fn neighbours(&self, position: Position) -> impl Iterator<Item = (Side, Position)> + '_ {
+        Side::ALL
+            .into_iter()
+            .filter_map(move |side| self.neighbour(position, side).map(|next| (side, next)))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(rows: usize, cols: usize, fill: T) -> Self {
+        Grid {
+            rows,
+            cols,
+            cells: vec![fill; rows * cols],
+        }
+    }
+
+    /// A grid as wide as the longest of `rows`, with the others padded with
+    /// `fill`.
+    pub fn from_rows(rows: &[Vec<T>], fill: T) -> Self {
+        let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+        Grid::from_fn(rows.len(), cols, |(row, col)| {
+            rows[row].get(col).cloned().unwrap_or_else(|| fill.clone())
+        })
+    }
+
+    /// The grid turned a quarter turn clockwise.
+    pub fn rotated_clockwise(&self) -> Self {
+        Grid::from_fn(self.cols, self.rows, |(row, col)| {
+            self[(self.rows - 1 - col, row)].clone()
+        })
+    }
+
+    /// The grid turned a quarter turn anticlockwise.
+    pub fn rotated_anticlockwise(&self) -> Self {
+        Grid::from_fn(self.cols, self.rows, |(row, col)| {
+            self[(col, self.cols - 1 - row)].clone()
+        })
+    }
+
+    pub fn transposed(&self) -> Self {
+        Grid::from_fn(self.cols, self.rows, |(row, col)| self[(col, row)].clone())
+    }
+}
+
+impl Grid<char> {
+    /// One grid cell per character of each line of `text`, with short lines
+    /// padded with spaces.
+    pub fn from_text(text: &str) -> Self {
+        let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        Grid::from_rows(&rows, ' ')
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        match self.index_of(position) {
+            Some(i) => &self.cells[i],
+            None => panic!(
+                "Position {:?} is outside a grid of {} by {}",
+                position, self.rows, self.cols
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        match self.index_of(position) {
+            Some(i) => &mut self.cells[i],
+            None => panic!(
+                "Position {:?} is outside a grid of {} by {}",
+                position, self.rows, self.cols
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotations_of_square_grids() {
+        let grid = Grid::from_text("ab\ncd");
+
+        assert_eq!(grid.rotated_clockwise(), Grid::from_text("ca\ndb"));
+        assert_eq!(grid.rotated_anticlockwise(), Grid::from_text("bd\nac"));
+        assert_eq!(grid.transposed(), Grid::from_text("ac\nbd"));
+    }
+
+    #[test]
+    fn rotations_of_non_square_grids() {
+        let grid = Grid::from_text("abc\ndef");
+
+        assert_eq!(grid.rotated_clockwise(), Grid::from_text("da\neb\nfc"));
+        assert_eq!(grid.rotated_anticlockwise(), Grid::from_text("cf\nbe\nad"));
+        assert_eq!(grid.transposed(), Grid::from_text("ad\nbe\ncf"));
+        assert_eq!(grid.rotated_clockwise().rotated_anticlockwise(), grid);
+        assert_eq!(
+            grid.rotated_clockwise().rotated_clockwise(),
+            Grid::from_text("fed\ncba")
+        );
+        assert_eq!(grid.transposed().transposed(), grid);
+    }
+
+    #[test]
+    fn neighbours_stop_at_the_edges() {
+        let grid = Grid::new(2, 3, 0);
+
+        assert_eq!(grid.neighbour((0, 1), Side::Up), None);
+        assert_eq!(grid.neighbour((1, 0), Side::Left), None);
+        assert_eq!(grid.neighbour((1, 1), Side::Down), None);
+        assert_eq!(grid.neighbour((0, 2), Side::Right), None);
+        assert_eq!(grid.neighbour((1, 1), Side::Up), Some((0, 1)));
+        assert_eq!(grid.neighbour((1, 1), Side::Left), Some((1, 0)));
+
+        assert_eq!(
+            grid.neighbours((0, 0)).collect::<Vec<_>>(),
+            [(Side::Right, (0, 1)), (Side::Down, (1, 0))]
+        );
+        assert_eq!(
+            grid.neighbours((1, 2)).collect::<Vec<_>>(),
+            [(Side::Up, (0, 2)), (Side::Left, (1, 1))]
+        );
+        assert_eq!(
+            grid.neighbours((0, 1)).collect::<Vec<_>>(),
+            [
+                (Side::Right, (0, 2)),
+                (Side::Down, (1, 1)),
+                (Side::Left, (0, 0))
+            ]
+        );
+        assert_eq!(Grid::new(1, 1, 0).neighbours((0, 0)).count(), 0);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod calendar;
+pub mod cli;
+pub mod crossword;
+pub mod days;
+pub mod encoding;
+pub mod grid;
+pub mod pipes;
+pub mod script;
+pub mod tz;
@@ -1,14 +1,51 @@
-mod days;
-
-use days::*;
+use i18n_puzzles::cli::Options;
+use i18n_puzzles::days::*;
+use i18n_puzzles::encoding::{
+    discover::discover,
+    pipeline::{Data, Pipeline},
+};
+use i18n_puzzles::tz::{
+    database::{tzdata_versions, TzDatabase, ZONEINFO_DIR},
+    transitions,
+};
+use std::process::ExitCode;
 use std::time::Instant;
-use std::{env, io};
+use std::{env, fs, io};
 
-fn main() {
+fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
+    let options = match Options::parse(&args[1..]) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let command: Option<fn(&Options) -> io::Result<String>> =
+        match options.positional().first().map(String::as_str) {
+            Some("tz") => Some(run_tz),
+            Some("decode") => Some(run_decode),
+            Some("discover") => Some(run_discover),
+            _ => None,
+        };
 
-    let days: Vec<u8> = match args.len() {
-        n if n >= 2 => args[1..]
+    if let Some(command) = command {
+        return match command(&options) {
+            Ok(output) => {
+                println!("{}", output);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let days: Vec<u8> = match options.positional().len() {
+        n if n >= 1 => options
+            .positional()
             .iter()
             .map(|x| {
                 x.parse()
@@ -24,7 +61,7 @@ fn main() {
         let func = get_day_solver(day);
 
         let time = Instant::now();
-        let solution = match func() {
+        let solution = match func(&options) {
             Ok(value) => value.to_string(),
             Err(e) => format!("Error running solution: {}", e),
         };
@@ -38,30 +75,135 @@ fn main() {
     }
 
     println!("Total runtime: {:.4} ms", runtime);
+
+    ExitCode::SUCCESS
+}
+
+/// `tz transitions <zone> [--version V] [--from Y] [--to Y]`
+fn run_tz(options: &Options) -> io::Result<String> {
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Usage: tz transitions <zone> [--version V] [--from Y] [--to Y]",
+        )
+    };
+
+    let (zone, versions) = match options.positional() {
+        [_, command, zone] if command == "transitions" => match options.get_list("version") {
+            Some(versions) => (zone, versions),
+            None => (zone, tzdata_versions()),
+        },
+        _ => return Err(usage()),
+    };
+
+    let year = |name: &str| -> io::Result<Option<i32>> {
+        options
+            .get(name)
+            .map(|year| year.parse().map_err(|_| usage()))
+            .transpose()
+    };
+
+    let database = TzDatabase::open(ZONEINFO_DIR, &versions)?;
+
+    transitions::report(&database, zone, &versions, year("from")?, year("to")?)
+}
+
+/// `decode <pipeline> <file>`, where the pipeline is written like
+/// `base64|utf16le|bits20|utf8-2279|bits28|utf8`.
+fn run_decode(options: &Options) -> io::Result<String> {
+    let (pipeline, path) = match options.positional() {
+        [_, pipeline, path] => (pipeline.parse::<Pipeline>()?, path),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Usage: decode <pipeline> <file>",
+            ))
+        }
+    };
+
+    pipeline
+        .apply(Data::Bytes(fs::read(path)?))
+        .map(|data| data.to_string())
+}
+
+/// `discover <file> [--depth N]` finds the layers of encoding of a file by
+/// trying chains of up to N transforms, six by default.
+fn run_discover(options: &Options) -> io::Result<String> {
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Usage: discover <file> [--depth N]",
+        )
+    };
+
+    let path = match options.positional() {
+        [_, path] => path,
+        _ => return Err(usage()),
+    };
+
+    let depth = match options.get("depth") {
+        Some(depth) => depth.parse().map_err(|_| usage())?,
+        None => 6,
+    };
+
+    let discovery = discover(Data::Bytes(fs::read(path)?), depth).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No chain of transforms gives text",
+        )
+    })?;
+
+    let labels = std::iter::once("input".to_string())
+        .chain(discovery.pipeline.transforms.iter().map(|t| t.to_string()));
+
+    let stages: Vec<String> = labels
+        .zip(&discovery.stages)
+        .map(|(label, data)| {
+            let shown = data.to_string();
+            let first_line = shown.lines().next().unwrap_or("");
+            let mut preview: String = first_line.chars().take(72).collect();
+            if first_line.chars().count() > 72 {
+                preview.push('…');
+            }
+            format!("  {:<10} {}", label, preview)
+        })
+        .collect();
+
+    Ok(format!(
+        "{} (score {:.2})\n{}\n{}",
+        discovery.pipeline,
+        discovery.score,
+        stages.join("\n"),
+        discovery
+            .stages
+            .last()
+            .map(Data::to_string)
+            .unwrap_or_default()
+    ))
 }
 
-fn get_day_solver(day: u8) -> fn() -> io::Result<String> {
+fn get_day_solver(day: u8) -> fn(&Options) -> io::Result<String> {
     match day {
-        1 => day01::solve,
-        2 => day02::solve,
-        3 => day03::solve,
-        4 => day04::solve,
-        5 => day05::solve,
-        6 => day06::solve,
-        7 => day07::solve,
-        8 => day08::solve,
-        9 => day09::solve,
-        10 => day10::solve,
-        11 => day11::solve,
-        12 => day12::solve,
-        13 => day13::solve,
-        14 => day14::solve,
-        15 => day15::solve,
-        16 => day16::solve,
-        17 => day17::solve,
-        18 => day18::solve,
-        19 => day19::solve,
-        20 => day20::solve,
+        1 => |_| day01::solve(),
+        2 => |_| day02::solve(),
+        3 => |_| day03::solve(),
+        4 => |_| day04::solve(),
+        5 => |_| day05::solve(),
+        6 => day06::solve_with,
+        7 => day07::solve_with,
+        8 => |_| day08::solve(),
+        9 => |_| day09::solve(),
+        10 => |_| day10::solve(),
+        11 => |_| day11::solve(),
+        12 => |_| day12::solve(),
+        13 => |_| day13::solve(),
+        14 => |_| day14::solve(),
+        15 => day15::solve_with,
+        16 => day16::solve_with,
+        17 => |_| day17::solve(),
+        18 => |_| day18::solve(),
+        19 => day19::solve_with,
+        20 => day20::solve_with,
         _ => unimplemented!(),
     }
 }
@@ -0,0 +1,312 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::grid::{Grid, Position};
+
+use super::{Line, Side, Tile};
+
+/// A rectangle drawn with box-drawing characters, as the rows and columns of
+/// its corners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: usize,
+    pub right: usize,
+}
+
+impl Frame {
+    fn interior_area(&self) -> usize {
+        (self.bottom - self.top - 1) * (self.right - self.left - 1)
+    }
+
+    /// Whether `other` lies entirely within the interior of the frame.
+    fn encloses(&self, other: &Frame) -> bool {
+        self.top < other.top
+            && self.left < other.left
+            && other.bottom < self.bottom
+            && other.right < self.right
+    }
+
+    /// The cells of each side of the frame between its corners.
+    fn edges(&self) -> [(Side, Vec<Position>); 4] {
+        let columns = self.left + 1..self.right;
+        let rows = self.top + 1..self.bottom;
+
+        [
+            (
+                Side::Up,
+                columns.clone().map(|col| (self.top, col)).collect(),
+            ),
+            (
+                Side::Right,
+                rows.clone().map(|row| (row, self.right)).collect(),
+            ),
+            (Side::Down, columns.map(|col| (self.bottom, col)).collect()),
+            (Side::Left, rows.map(|row| (row, self.left)).collect()),
+        ]
+    }
+}
+
+/// The part of some text holding the grid: the interior of the innermost of
+/// the frames around it, and the cells where the border of that frame is
+/// broken, through which the path enters and leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playfield {
+    /// Every frame around the grid, outermost first.
+    pub frames: Vec<Frame>,
+    /// The cells next to a break in the innermost frame and the side facing
+    /// it, relative to the grid and in reading order.
+    pub openings: Vec<(Position, Side)>,
+}
+
+impl Playfield {
+    pub fn bounds(&self) -> Frame {
+        self.frames[self.frames.len() - 1]
+    }
+
+    pub fn rows(&self) -> usize {
+        let bounds = self.bounds();
+        bounds.bottom - bounds.top - 1
+    }
+
+    pub fn cols(&self) -> usize {
+        let bounds = self.bounds();
+        bounds.right - bounds.left - 1
+    }
+
+    pub fn entry(&self) -> Option<(Position, Side)> {
+        self.openings.first().copied()
+    }
+
+    pub fn exit(&self) -> Option<(Position, Side)> {
+        self.openings.last().copied()
+    }
+
+    /// The characters inside the innermost frame.
+    pub fn grid(&self, text: &Grid<char>) -> Grid<char> {
+        let bounds = self.bounds();
+
+        Grid::from_fn(self.rows(), self.cols(), |(row, col)| {
+            text[(bounds.top + 1 + row, bounds.left + 1 + col)]
+        })
+    }
+}
+
+fn char_at(text: &Grid<char>, position: Position) -> char {
+    text.get(position).copied().unwrap_or(' ')
+}
+
+fn tile_at(text: &Grid<char>, position: Position) -> Tile {
+    Tile::from_char(char_at(text, position)).unwrap_or(Tile::EMPTY)
+}
+
+/// Whether `tile` is a corner joining lines through exactly `sides`, such as
+/// `┌` or `╔` for the right and bottom.
+fn is_corner(tile: Tile, sides: [Side; 2]) -> bool {
+    Side::ALL
+        .into_iter()
+        .all(|side| (tile.line(side) != Line::None) == sides.contains(&side))
+}
+
+/// Whether `position` carries the border along `side` of a frame unbroken,
+/// which a line joining it from outside does not interrupt.
+fn is_edge(text: &Grid<char>, position: Position, side: Side) -> bool {
+    let tile = tile_at(text, position);
+    let inward = side.opposite();
+    let along = match side {
+        Side::Up | Side::Down => [Side::Left, Side::Right],
+        Side::Left | Side::Right => [Side::Up, Side::Down],
+    };
+
+    along.iter().all(|&side| tile.line(side) != Line::None) && tile.line(inward) == Line::None
+}
+
+/// Every rectangle in `text` with a corner at each corner and at least half
+/// of each side unbroken.
+pub fn find_frames(text: &Grid<char>) -> Vec<Frame> {
+    let corners = |sides: [Side; 2]| -> Vec<Position> {
+        text.positions()
+            .filter(|&position| is_corner(tile_at(text, position), sides))
+            .collect()
+    };
+
+    let top_rights = corners([Side::Down, Side::Left]);
+    let bottom_lefts = corners([Side::Up, Side::Right]);
+    let mut frames = Vec::new();
+
+    for (top, left) in corners([Side::Right, Side::Down]) {
+        for &(_, right) in top_rights
+            .iter()
+            .filter(|&&(row, col)| row == top && col > left + 1)
+        {
+            for &(bottom, _) in bottom_lefts
+                .iter()
+                .filter(|&&(row, col)| col == left && row > top + 1)
+            {
+                if !is_corner(tile_at(text, (bottom, right)), [Side::Up, Side::Left]) {
+                    continue;
+                }
+
+                let frame = Frame {
+                    top,
+                    left,
+                    bottom,
+                    right,
+                };
+
+                let unbroken = frame.edges().iter().all(|(side, cells)| {
+                    let edges = cells
+                        .iter()
+                        .filter(|&&position| is_edge(text, position, *side))
+                        .count();
+                    2 * edges >= cells.len()
+                });
+
+                if unbroken {
+                    frames.push(frame);
+                }
+            }
+        }
+    }
+
+    frames
+}
+
+/// Whether `c` may lie between a frame and the frame within it: space,
+/// shading such as `░`, or a straight line leading through both.
+fn is_margin(c: char) -> bool {
+    c.is_whitespace()
+        || ('\u{2580}'..='\u{259F}').contains(&c)
+        || matches!(c, '│' | '─' | '║' | '═')
+}
+
+/// Finds the grid in `text` inside any number of nested frames, ignoring
+/// whatever surrounds them, such as shadows drawn with `░`. Starting from the
+/// largest frame, it descends into a frame within the current one as long as
+/// nothing but margin lies between the two, so that boxes which happen to be
+/// drawn by the tiles of the grid are not mistaken for frames.
+pub fn detect_playfield(text: &Grid<char>) -> Result<Playfield> {
+    let candidates = find_frames(text);
+
+    let outermost = candidates
+        .iter()
+        .max_by_key(|frame| frame.interior_area())
+        .copied()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No frame found around the grid"))?;
+
+    let mut frames = vec![outermost];
+
+    loop {
+        let current = frames[frames.len() - 1];
+        let inner = candidates.iter().find(|frame| {
+            current.encloses(frame)
+                && (current.top + 1..current.bottom).all(|row| {
+                    (current.left + 1..current.right).all(|col| {
+                        let inside = (frame.top..=frame.bottom).contains(&row)
+                            && (frame.left..=frame.right).contains(&col);
+                        inside || is_margin(char_at(text, (row, col)))
+                    })
+                })
+        });
+
+        match inner {
+            Some(frame) => frames.push(*frame),
+            None => break,
+        }
+    }
+
+    let bounds = frames[frames.len() - 1];
+    let mut openings: Vec<(Position, Side)> = bounds
+        .edges()
+        .into_iter()
+        .flat_map(|(side, cells)| {
+            cells
+                .into_iter()
+                .filter(move |&position| !is_edge(text, position, side))
+                .map(move |(row, col)| {
+                    let position = match side {
+                        Side::Up => (0, col - bounds.left - 1),
+                        Side::Right => (row - bounds.top - 1, bounds.right - bounds.left - 2),
+                        Side::Down => (bounds.bottom - bounds.top - 2, col - bounds.left - 1),
+                        Side::Left => (row - bounds.top - 1, 0),
+                    };
+
+                    (position, side)
+                })
+        })
+        .collect();
+
+    openings.sort_by_key(|&(position, _)| position);
+
+    Ok(Playfield { frames, openings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A double frame with a break at the top and bottom, inside a light one
+    /// with a shadow, between lines of other text.
+    const NESTED: &str = "\
+header
+ ┌───────┐
+ │ ╔═ ═╗ │░
+ │ ║│─┐║ │░
+ │ ║┘ │║ │░
+ │ ╚═ ═╝ │░
+ └───────┘░
+  ░░░░░░░░░
+footer";
+
+    #[test]
+    fn nested_frames() {
+        let text = Grid::from_text(NESTED);
+        let playfield = detect_playfield(&text).unwrap();
+
+        assert_eq!(
+            playfield.frames,
+            vec![
+                Frame {
+                    top: 1,
+                    left: 1,
+                    bottom: 6,
+                    right: 9
+                },
+                Frame {
+                    top: 2,
+                    left: 3,
+                    bottom: 5,
+                    right: 7
+                },
+            ]
+        );
+        assert_eq!((playfield.rows(), playfield.cols()), (2, 3));
+        assert_eq!(playfield.entry(), Some(((0, 1), Side::Up)));
+        assert_eq!(playfield.exit(), Some(((1, 1), Side::Down)));
+        assert_eq!(playfield.grid(&text), Grid::from_text("│─┐\n┘ │"));
+    }
+
+    #[test]
+    fn boxes_drawn_by_the_tiles_are_not_frames() {
+        let text = Grid::from_text(
+            "\
+╔═════╗
+║┤┌─┐ ║
+║ │ │┬║
+║ └─┘ ║
+╚══ ══╝",
+        );
+        let playfield = detect_playfield(&text).unwrap();
+
+        assert_eq!(find_frames(&text).len(), 2);
+        assert_eq!(playfield.frames.len(), 1);
+        assert_eq!((playfield.rows(), playfield.cols()), (3, 5));
+        assert_eq!(playfield.openings, vec![((2, 2), Side::Down)]);
+    }
+
+    #[test]
+    fn sides_must_be_mostly_unbroken() {
+        assert_eq!(find_frames(&Grid::from_text("┌─ ─┐\n│   │\n└ ─ ┘")), vec![]);
+        assert!(detect_playfield(&Grid::from_text("no frame here")).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+pub mod frame;
+pub mod render;
+pub mod solver;
+
+use crate::grid::{Grid, Position};
+
+pub use crate::grid::Side;
+
+/// The kind of line leaving a cell through one of its sides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Line {
+    #[default]
+    None,
+    Single,
+    Double,
+}
+
+/// A box-drawing tile as the lines leaving it through each side, two bits a
+/// side with the top in the lowest, so turning it is a bit rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Tile(u8);
+
+/// Every tile drawn with light and double lines in the Box Drawing block, as
+/// its lines on the top, right, bottom and left.
+const BOX_DRAWING: [(char, [u8; 4]); 44] = [
+    ('─', [0, 1, 0, 1]),
+    ('│', [1, 0, 1, 0]),
+    ('┌', [0, 1, 1, 0]),
+    ('┐', [0, 0, 1, 1]),
+    ('└', [1, 1, 0, 0]),
+    ('┘', [1, 0, 0, 1]),
+    ('├', [1, 1, 1, 0]),
+    ('┤', [1, 0, 1, 1]),
+    ('┬', [0, 1, 1, 1]),
+    ('┴', [1, 1, 0, 1]),
+    ('┼', [1, 1, 1, 1]),
+    ('═', [0, 2, 0, 2]),
+    ('║', [2, 0, 2, 0]),
+    ('╒', [0, 2, 1, 0]),
+    ('╓', [0, 1, 2, 0]),
+    ('╔', [0, 2, 2, 0]),
+    ('╕', [0, 0, 1, 2]),
+    ('╖', [0, 0, 2, 1]),
+    ('╗', [0, 0, 2, 2]),
+    ('╘', [1, 2, 0, 0]),
+    ('╙', [2, 1, 0, 0]),
+    ('╚', [2, 2, 0, 0]),
+    ('╛', [1, 0, 0, 2]),
+    ('╜', [2, 0, 0, 1]),
+    ('╝', [2, 0, 0, 2]),
+    ('╞', [1, 2, 1, 0]),
+    ('╟', [2, 1, 2, 0]),
+    ('╠', [2, 2, 2, 0]),
+    ('╡', [1, 0, 1, 2]),
+    ('╢', [2, 0, 2, 1]),
+    ('╣', [2, 0, 2, 2]),
+    ('╤', [0, 2, 1, 2]),
+    ('╥', [0, 1, 2, 1]),
+    ('╦', [0, 2, 2, 2]),
+    ('╧', [1, 2, 0, 2]),
+    ('╨', [2, 1, 0, 1]),
+    ('╩', [2, 2, 0, 2]),
+    ('╪', [1, 2, 1, 2]),
+    ('╫', [2, 1, 2, 1]),
+    ('╬', [2, 2, 2, 2]),
+    ('╴', [0, 0, 0, 1]),
+    ('╵', [1, 0, 0, 0]),
+    ('╶', [0, 1, 0, 0]),
+    ('╷', [0, 0, 1, 0]),
+];
+
+/// The tiles that may be turned: the straight lines, corners and T-junctions
+/// drawn wholly in light or wholly in double lines, and the T-junctions
+/// mixing the two. Crosses, corners mixing the two and half-lines stay as
+/// they are.
+const ROTATABLE: &str = "─│┌┐└┘├┤┬┴═║╔╗╚╝╠╣╦╩╞╡╥╨╟╢╤╧";
+
+/// Where the two bits for `side` start in a [`Tile`].
+fn shift(side: Side) -> u32 {
+    2 * side as u32
+}
+
+impl Tile {
+    pub const EMPTY: Tile = Tile(0);
+
+    pub fn new(lines: [Line; 4]) -> Self {
+        Side::ALL
+            .into_iter()
+            .zip(lines)
+            .fold(Tile::EMPTY, |tile, (side, line)| tile.with(side, line))
+    }
+
+    fn from_bits(lines: [u8; 4]) -> Self {
+        Tile(lines[0] | lines[1] << 2 | lines[2] << 4 | lines[3] << 6)
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        BOX_DRAWING
+            .iter()
+            .find(|(box_char, _)| *box_char == c)
+            .map(|(_, lines)| Tile::from_bits(*lines))
+    }
+
+    /// The box-drawing character for the tile, if Unicode has one.
+    pub fn to_char(self) -> Option<char> {
+        BOX_DRAWING
+            .iter()
+            .find(|(_, lines)| Tile::from_bits(*lines) == self)
+            .map(|(box_char, _)| *box_char)
+    }
+
+    pub fn line(&self, side: Side) -> Line {
+        match self.0 >> shift(side) & 0b11 {
+            0 => Line::None,
+            1 => Line::Single,
+            _ => Line::Double,
+        }
+    }
+
+    pub fn with(self, side: Side, line: Line) -> Self {
+        let bits = match line {
+            Line::None => 0,
+            Line::Single => 1,
+            Line::Double => 2,
+        };
+
+        Tile(self.0 & !(0b11 << shift(side)) | bits << shift(side))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Tile::EMPTY
+    }
+
+    /// The tile turned clockwise by `turns` quarter turns.
+    pub fn rotated(self, turns: u8) -> Self {
+        Tile(self.0.rotate_left(2 * (turns % 4) as u32))
+    }
+
+    /// The quarter turns giving each distinct orientation of the tile, fewest
+    /// first: one for a cross, two for a straight line and four otherwise.
+    pub fn orientations(self) -> Vec<u8> {
+        let mut seen = Vec::new();
+
+        (0..4)
+            .filter(|&turns| {
+                let tile = self.rotated(turns);
+                let new = !seen.contains(&tile);
+                seen.push(tile);
+                new
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub tile: Tile,
+    /// Whether the tile may be turned, or must stay as it is.
+    pub rotatable: bool,
+}
+
+/// A rectangular grid of tiles to turn until every line meets a line of the
+/// same kind, and only the openings lead out of the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    cells: Grid<Cell>,
+    openings: Vec<(Position, Side)>,
+}
+
+impl Puzzle {
+    /// A puzzle from a grid of characters, where box-drawing characters are
+    /// tiles, turnable if in [`ROTATABLE`], and anything else is an empty
+    /// cell.
+    pub fn from_grid(grid: &Grid<char>) -> Self {
+        let cells = grid.map(|c| match Tile::from_char(*c) {
+            Some(tile) => Cell {
+                tile,
+                rotatable: ROTATABLE.contains(*c),
+            },
+            None => Cell {
+                tile: Tile::EMPTY,
+                rotatable: false,
+            },
+        });
+
+        Puzzle {
+            cells,
+            openings: Vec::new(),
+        }
+    }
+
+    pub fn cells(&self) -> &Grid<Cell> {
+        &self.cells
+    }
+
+    pub fn cell(&self, position: Position) -> Option<&Cell> {
+        self.cells.get(position)
+    }
+
+    /// Places `tile` at `position` and keeps it from being turned.
+    pub fn fix(&mut self, position: Position, tile: Tile) {
+        if let Some(cell) = self.cells.get_mut(position) {
+            *cell = Cell {
+                tile,
+                rotatable: false,
+            };
+        }
+    }
+
+    /// Lets the tile at `position` lead out of the grid through `side`, such
+    /// as where a path enters or leaves it.
+    pub fn open(&mut self, position: Position, side: Side) {
+        self.openings.push((position, side));
+    }
+
+    pub fn is_open(&self, position: Position, side: Side) -> bool {
+        self.openings.contains(&(position, side))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(c: char) -> Tile {
+        Tile::from_char(c).unwrap()
+    }
+
+    #[test]
+    fn characters_round_trip() {
+        for (c, _) in BOX_DRAWING {
+            assert_eq!(tile(c).to_char(), Some(c));
+        }
+        assert_eq!(Tile::from_char('x'), None);
+    }
+
+    #[test]
+    fn turning_is_clockwise() {
+        assert_eq!(tile('┌').rotated(1), tile('┐'));
+        assert_eq!(tile('┌').rotated(2), tile('┘'));
+        assert_eq!(tile('╞').rotated(1), tile('╥'));
+        assert_eq!(tile('─').rotated(5), tile('│'));
+        assert_eq!(tile('┼').orientations(), vec![0]);
+        assert_eq!(tile('║').orientations(), vec![0, 1]);
+        assert_eq!(tile('╢').orientations(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn only_the_rotatable_tiles_may_turn() {
+        let puzzle = Puzzle::from_grid(&Grid::from_text("┌╢┼╪╒╴x"));
+        let rotatable: Vec<bool> = (0..7)
+            .map(|col| puzzle.cell((0, col)).unwrap().rotatable)
+            .collect();
+
+        assert_eq!(
+            rotatable,
+            vec![true, true, false, false, false, false, false]
+        );
+        assert_eq!(puzzle.cell((0, 3)).unwrap().tile, tile('╪'));
+        assert!(puzzle.cell((0, 6)).unwrap().tile.is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use crate::grid::{Grid, Position};
+
+use super::{Line, Side, Tile};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    /// Text for the caller to encode in the codepage the grid came in.
+    Codepage,
+    Html,
+    Svg,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(label: &str) -> Result<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(Format::Text),
+            "codepage" => Ok(Format::Codepage),
+            "html" => Ok(Format::Html),
+            "svg" => Ok(Format::Svg),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown format {:?}", label),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Text => "text",
+            Format::Codepage => "codepage",
+            Format::Html => "html",
+            Format::Svg => "svg",
+        })
+    }
+}
+
+/// What to show of a solved grid: the tiles as they end up, or where they
+/// were turned and by how many quarter turns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum View {
+    #[default]
+    Solved,
+    Diff,
+}
+
+impl FromStr for View {
+    type Err = Error;
+
+    fn from_str(label: &str) -> Result<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "solved" => Ok(View::Solved),
+            "diff" => Ok(View::Diff),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown view {:?}", label),
+            )),
+        }
+    }
+}
+
+/// Highlights for cells turned once, twice and three times.
+const TURN_COLOURS: [&str; 3] = ["#ffe08a", "#ffb36b", "#ff7b6b"];
+
+const CELL_SIZE: usize = 20;
+
+/// Draws `grid`, where `turns` holds the quarter turns each cell took.
+pub fn render(grid: &Grid<char>, turns: &Grid<u8>, format: Format, view: View) -> String {
+    match format {
+        Format::Text | Format::Codepage => text(grid, turns, view),
+        Format::Html => html(grid, turns, view),
+        Format::Svg => svg(grid, turns, view),
+    }
+}
+
+fn turns_at(turns: &Grid<u8>, position: Position) -> u8 {
+    turns.get(position).copied().unwrap_or(0)
+}
+
+fn escape(c: char) -> String {
+    match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        c => c.to_string(),
+    }
+}
+
+/// One line of characters a row. The diff view puts the number of turns in
+/// place of each turned tile.
+pub fn text(grid: &Grid<char>, turns: &Grid<u8>, view: View) -> String {
+    grid.iter_rows()
+        .enumerate()
+        .map(|(row, cells)| {
+            let line: String = cells
+                .iter()
+                .enumerate()
+                .map(|(col, c)| match (view, turns_at(turns, (row, col))) {
+                    (View::Diff, turned @ 1..) => char::from_digit(turned as u32, 10).unwrap_or(*c),
+                    _ => *c,
+                })
+                .collect();
+
+            line + "\n"
+        })
+        .collect()
+}
+
+/// A page with the grid as preformatted text. The diff view shades each
+/// turned tile by its number of turns, shown again when hovering over it.
+pub fn html(grid: &Grid<char>, turns: &Grid<u8>, view: View) -> String {
+    let mut body = String::new();
+
+    for (row, cells) in grid.iter_rows().enumerate() {
+        for (col, c) in cells.iter().enumerate() {
+            let escaped = escape(*c);
+
+            match (view, turns_at(turns, (row, col))) {
+                (View::Diff, turned @ 1..) => body.push_str(&format!(
+                    "<span class=\"turned-{}\" title=\"{} turn{}\">{}</span>",
+                    turned,
+                    turned,
+                    if turned == 1 { "" } else { "s" },
+                    escaped
+                )),
+                _ => body.push_str(&escaped),
+            }
+        }
+
+        body.push('\n');
+    }
+
+    let styles: String = TURN_COLOURS
+        .iter()
+        .enumerate()
+        .map(|(i, colour)| format!(".turned-{} {{ background: {}; }}\n", i + 1, colour))
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\npre {{ font-family: monospace; line-height: 1; }}\n{}</style>\n</head>\n<body>\n<pre>\n{}</pre>\n</body>\n</html>\n",
+        styles, body
+    )
+}
+
+/// An image with each tile drawn as strokes from the middle of the cell to
+/// the middle of each side it leads out of, and any other character as text.
+/// The diff view shades each turned tile and writes its number of turns in
+/// the corner.
+pub fn svg(grid: &Grid<char>, turns: &Grid<u8>, view: View) -> String {
+    let mut shapes = String::new();
+
+    for (row, cells) in grid.iter_rows().enumerate() {
+        for (col, c) in cells.iter().enumerate() {
+            let (x, y) = (col * CELL_SIZE, row * CELL_SIZE);
+            let turned = turns_at(turns, (row, col));
+
+            if view == View::Diff && turned > 0 {
+                shapes.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    x,
+                    y,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                    TURN_COLOURS[(turned as usize - 1) % TURN_COLOURS.len()]
+                ));
+                shapes.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"6\">{}</text>\n",
+                    x + 1,
+                    y + 6,
+                    turned
+                ));
+            }
+
+            match Tile::from_char(*c) {
+                Some(tile) => shapes.push_str(&strokes(tile, x, y)),
+                None if c.is_whitespace() => {}
+                None => shapes.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"14\" text-anchor=\"middle\">{}</text>\n",
+                    x + CELL_SIZE / 2,
+                    y + CELL_SIZE * 3 / 4,
+                    escape(*c)
+                )),
+            }
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{}</svg>\n",
+        grid.cols() * CELL_SIZE,
+        grid.rows() * CELL_SIZE,
+        shapes
+    )
+}
+
+/// The strokes of `tile` in the cell at `x`, `y`. A double line is two
+/// strokes either side of where a single one would be.
+fn strokes(tile: Tile, x: usize, y: usize) -> String {
+    let centre = (
+        x as f64 + CELL_SIZE as f64 / 2.0,
+        y as f64 + CELL_SIZE as f64 / 2.0,
+    );
+    let half = CELL_SIZE as f64 / 2.0;
+
+    Side::ALL
+        .into_iter()
+        .flat_map(|side| {
+            let (dx, dy) = match side {
+                Side::Up => (0.0, -half),
+                Side::Right => (half, 0.0),
+                Side::Down => (0.0, half),
+                Side::Left => (-half, 0.0),
+            };
+
+            let offsets: &[f64] = match tile.line(side) {
+                Line::None => &[],
+                Line::Single => &[0.0],
+                Line::Double => &[-3.0, 3.0],
+            };
+
+            offsets.iter().map(move |offset| {
+                let (ox, oy) = match dx == 0.0 {
+                    true => (*offset, 0.0),
+                    false => (0.0, *offset),
+                };
+
+                format!(
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"2\" stroke-linecap=\"square\"/>\n",
+                    centre.0 + ox,
+                    centre.1 + oy,
+                    centre.0 + ox + dx,
+                    centre.1 + oy + dy
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turns(rows: &[Vec<u8>]) -> Grid<u8> {
+        Grid::from_rows(rows, 0)
+    }
+
+    #[test]
+    fn text_diff_shows_turns_in_place_of_tiles() {
+        let grid = Grid::from_text("─┐\n └");
+        let turns = turns(&[vec![1, 0], vec![0, 2]]);
+
+        assert_eq!(text(&grid, &turns, View::Solved), "─┐\n └\n");
+        assert_eq!(text(&grid, &turns, View::Diff), "1┐\n 2\n");
+        assert_eq!(
+            render(&grid, &turns, Format::Codepage, View::Diff),
+            "1┐\n 2\n"
+        );
+    }
+
+    #[test]
+    fn html_escapes_and_marks_turned_tiles() {
+        let grid = Grid::from_text("<&\n─┐");
+        let turns = turns(&[vec![0, 3], vec![1, 0]]);
+
+        assert_eq!(
+            html(&grid, &turns, View::Diff),
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n\
+             pre { font-family: monospace; line-height: 1; }\n\
+             .turned-1 { background: #ffe08a; }\n\
+             .turned-2 { background: #ffb36b; }\n\
+             .turned-3 { background: #ff7b6b; }\n\
+             </style>\n</head>\n<body>\n<pre>\n\
+             &lt;<span class=\"turned-3\" title=\"3 turns\">&amp;</span>\n\
+             <span class=\"turned-1\" title=\"1 turn\">─</span>┐\n\
+             </pre>\n</body>\n</html>\n"
+        );
+        assert!(html(&grid, &turns, View::Solved).contains("<pre>\n&lt;&amp;\n─┐\n</pre>"));
+    }
+
+    #[test]
+    fn svg_draws_double_lines_as_two_strokes() {
+        let line = |x1, y1, x2, y2| {
+            format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"2\" stroke-linecap=\"square\"/>\n",
+                x1, y1, x2, y2
+            )
+        };
+
+        assert_eq!(
+            strokes(Tile::from_char('╴').unwrap(), 0, 0),
+            line(10, 10, 0, 10)
+        );
+        assert_eq!(
+            strokes(Tile::from_char('═').unwrap(), 20, 0),
+            [
+                line(30, 7, 40, 7),
+                line(30, 13, 40, 13),
+                line(30, 7, 20, 7),
+                line(30, 13, 20, 13)
+            ]
+            .concat()
+        );
+
+        let grid = Grid::from_text("═");
+        let image = svg(&grid, &turns(&[vec![2]]), View::Diff);
+        assert_eq!(image.matches("<line ").count(), 4);
+        assert!(
+            image.contains("<rect x=\"0\" y=\"0\" width=\"20\" height=\"20\" fill=\"#ffb36b\"/>")
+        );
+        assert!(image
+            .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"20\">"));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::grid::{Grid, Position};
+
+use super::{Line, Puzzle, Side, Tile};
+
+/// The tiles of a solved puzzle and the quarter turns each one took.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub turns: Grid<u8>,
+    pub tiles: Grid<Tile>,
+}
+
+impl Solution {
+    pub fn total_turns(&self) -> usize {
+        self.turns.iter().map(|(_, &turns)| turns as usize).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Unsolvable,
+    Unique(Solution),
+    /// Two of the solutions, when there are more than one.
+    Ambiguous(Solution, Solution),
+}
+
+/// The quarter turns each cell may still take, one bit per number of turns.
+type Domains = Grid<u8>;
+
+/// Solves `puzzle` by narrowing the turns each tile may take to those whose
+/// lines its neighbours can still meet, and guessing for the most constrained
+/// tile whenever that stalls. Every tile is turned as little as possible, and
+/// the search stops at a second solution.
+pub fn solve(puzzle: &Puzzle) -> Outcome {
+    let domains = puzzle.cells.map(|cell| match cell.rotatable {
+        true => cell
+            .tile
+            .orientations()
+            .into_iter()
+            .fold(0, |domain, turns| domain | 1 << turns),
+        false => 1,
+    });
+
+    let mut solutions = Vec::new();
+    let queue = domains.positions().collect();
+    search(puzzle, domains, queue, &mut solutions);
+
+    let mut solutions = solutions.into_iter();
+    match (solutions.next(), solutions.next()) {
+        (Some(first), Some(second)) => Outcome::Ambiguous(first, second),
+        (Some(solution), None) => Outcome::Unique(solution),
+        _ => Outcome::Unsolvable,
+    }
+}
+
+fn search(
+    puzzle: &Puzzle,
+    mut domains: Domains,
+    queue: Vec<Position>,
+    solutions: &mut Vec<Solution>,
+) {
+    if !propagate(puzzle, &mut domains, queue) {
+        return;
+    }
+
+    let undecided = domains
+        .iter()
+        .filter(|(_, domain)| domain.count_ones() > 1)
+        .min_by_key(|(_, domain)| domain.count_ones())
+        .map(|(position, _)| position);
+
+    let Some(position) = undecided else {
+        let turns = domains.map(|domain| domain.trailing_zeros() as u8);
+        let tiles = Grid::from_fn(turns.rows(), turns.cols(), |position| {
+            puzzle.cells[position].tile.rotated(turns[position])
+        });
+
+        solutions.push(Solution { turns, tiles });
+        return;
+    };
+
+    for turns in (0..4).filter(|turns| domains[position] & 1 << turns != 0) {
+        if solutions.len() > 1 {
+            return;
+        }
+
+        let mut guess = domains.clone();
+        guess[position] = 1 << turns;
+
+        let queue = domains.neighbours(position).map(|(_, next)| next).collect();
+        search(puzzle, guess, queue, solutions);
+    }
+}
+
+/// Removes the turns no longer possible from the domains of `queue` and of
+/// every cell next to one that changed. False if a cell has none left.
+fn propagate(puzzle: &Puzzle, domains: &mut Domains, mut queue: Vec<Position>) -> bool {
+    let mut queued = domains.map(|_| false);
+    queue.iter().for_each(|&position| queued[position] = true);
+
+    while let Some(position) = queue.pop() {
+        queued[position] = false;
+
+        let domain = (0..4)
+            .filter(|&turns| {
+                domains[position] & 1 << turns != 0 && fits(puzzle, domains, position, turns)
+            })
+            .fold(0, |domain, turns| domain | 1 << turns);
+
+        if domain == 0 {
+            return false;
+        }
+
+        if domain != domains[position] {
+            domains[position] = domain;
+
+            for (_, next) in puzzle.cells.neighbours(position) {
+                if !queued[next] {
+                    queued[next] = true;
+                    queue.push(next);
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Whether every line of the tile at `position` turned by `turns` can still
+/// meet its neighbour, and no line leads out of the grid except at an opening.
+fn fits(puzzle: &Puzzle, domains: &Domains, position: Position, turns: u8) -> bool {
+    let tile = puzzle.cells[position].tile.rotated(turns);
+
+    Side::ALL.into_iter().all(|side| {
+        let line = tile.line(side);
+
+        match puzzle.cells.neighbour(position, side) {
+            Some(next) => (0..4).any(|turns| {
+                domains[next] & 1 << turns != 0
+                    && puzzle.cells[next].tile.rotated(turns).line(side.opposite()) == line
+            }),
+            None => (line != Line::None) == puzzle.is_open(position, side),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in from the left of the top row, across and down, and out to
+    /// the right of the bottom row: `─┐` over ` └`, one, one and two quarter
+    /// turns away.
+    fn puzzle() -> Puzzle {
+        let mut puzzle = Puzzle::from_grid(&Grid::from_text("│┌\n ┐"));
+        puzzle.open((0, 0), Side::Left);
+        puzzle.open((1, 1), Side::Right);
+        puzzle
+    }
+
+    fn chars(solution: &Solution) -> String {
+        (0..solution.tiles.rows())
+            .map(|row| {
+                (0..solution.tiles.cols())
+                    .map(|col| solution.tiles[(row, col)].to_char().unwrap_or(' '))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn unique_solution() {
+        let Outcome::Unique(solution) = solve(&puzzle()) else {
+            panic!("Expected a unique solution");
+        };
+
+        assert_eq!(chars(&solution), "─┐\n └");
+        assert_eq!(solution.turns[(0, 0)], 1);
+        assert_eq!(solution.turns[(0, 1)], 1);
+        assert_eq!(solution.turns[(1, 1)], 2);
+        assert_eq!(solution.total_turns(), 4);
+    }
+
+    #[test]
+    fn lines_leave_the_grid_only_at_openings() {
+        let mut closed = Puzzle::from_grid(&Grid::from_text("│┌\n ┐"));
+        closed.open((0, 0), Side::Left);
+
+        assert_eq!(solve(&closed), Outcome::Unsolvable);
+    }
+
+    #[test]
+    fn fixed_tiles_stay_as_they_are() {
+        let mut puzzle = puzzle();
+        puzzle.fix((0, 0), Tile::from_char('│').unwrap());
+
+        assert_eq!(solve(&puzzle), Outcome::Unsolvable);
+    }
+
+    #[test]
+    fn symmetric_paths_are_ambiguous() {
+        // Two paths in from the top meet a ladder of T-junctions, which can
+        // join them either along the top row or through the middle one.
+        let mut puzzle = Puzzle::from_grid(&Grid::from_text("┌┤├┐\n├┤├┤\n└┘└┘"));
+        puzzle.open((0, 1), Side::Up);
+        puzzle.open((0, 2), Side::Up);
+
+        let Outcome::Ambiguous(first, second) = solve(&puzzle) else {
+            panic!("Expected two solutions");
+        };
+
+        let mut found = [chars(&first), chars(&second)];
+        found.sort();
+        assert_eq!(found, ["┌┤├┐\n├┤├┤\n└┘└┘", "┌┴┴┐\n├┬┬┤\n└┘└┘"]);
+        assert_eq!(first.total_turns().min(second.total_turns()), 0);
+    }
+}
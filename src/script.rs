@@ -0,0 +1,233 @@
+use std::collections::BTreeSet;
+
+use unicode_script::{Script, ScriptExtension, UnicodeScript};
+
+/// How suspicious the mix of scripts in a string is, following the
+/// restriction levels of UTS #39 section 5.2, least suspicious first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RestrictionLevel {
+    Ascii,
+    SingleScript,
+    /// Latin mixed with Han and the scripts written alongside it in
+    /// Japanese, Chinese or Korean.
+    HighlyRestrictive,
+    /// Latin mixed with one other recommended script besides Cyrillic and
+    /// Greek, whose letters are too easily confused with Latin ones.
+    ModeratelyRestrictive,
+    MinimallyRestrictive,
+}
+
+/// Scripts as ISO 15924 codes, which also name the combinations UTS #39
+/// counts as one script: `Jpan`, `Kore` and `Hanb`.
+type ScriptSet = BTreeSet<&'static str>;
+
+const HIGHLY_RESTRICTIVE: [&[&str]; 3] = [
+    &["Latn", "Hani", "Hira", "Kana"],
+    &["Latn", "Hani", "Bopo"],
+    &["Latn", "Hani", "Hang"],
+];
+
+/// The scripts of `c`, or `None` if it is used with every script.
+fn scripts(c: char) -> Option<ScriptSet> {
+    let extension = ScriptExtension::from(c);
+
+    match extension.is_common() || extension.is_inherited() {
+        true => None,
+        false => Some(extension.iter().map(Script::short_name).collect()),
+    }
+}
+
+/// Adds the combined scripts that each of `scripts` is part of.
+fn augment(mut scripts: ScriptSet) -> ScriptSet {
+    let additions: &[(&str, &[&str])] = &[
+        ("Hani", &["Hanb", "Jpan", "Kore"]),
+        ("Hira", &["Jpan"]),
+        ("Kana", &["Jpan"]),
+        ("Hang", &["Kore"]),
+        ("Bopo", &["Hanb"]),
+    ];
+
+    for (script, combined) in additions {
+        if scripts.contains(script) {
+            scripts.extend(combined.iter());
+        }
+    }
+
+    scripts
+}
+
+/// The scripts every character of `text` can be written in, after counting
+/// Han with kana, Hangul or Bopomofo as one script. Empty when the text mixes
+/// scripts, and `None` when it only has characters common to all of them.
+pub fn resolved_scripts(text: &str) -> Option<ScriptSet> {
+    text.chars()
+        .filter_map(scripts)
+        .map(augment)
+        .reduce(|resolved, scripts| resolved.intersection(&scripts).copied().collect())
+}
+
+pub fn is_mixed_script(text: &str) -> bool {
+    resolved_scripts(text).is_some_and(|resolved| resolved.is_empty())
+}
+
+/// Whether every character of `text` is in one of `cover`.
+fn covered_by(text: &str, cover: &[&str]) -> bool {
+    text.chars()
+        .filter_map(scripts)
+        .all(|scripts| cover.iter().any(|script| scripts.contains(script)))
+}
+
+pub fn restriction_level(text: &str) -> RestrictionLevel {
+    if text.is_ascii() {
+        return RestrictionLevel::Ascii;
+    }
+
+    if !is_mixed_script(text) {
+        return RestrictionLevel::SingleScript;
+    }
+
+    if HIGHLY_RESTRICTIVE
+        .iter()
+        .any(|cover| covered_by(text, cover))
+    {
+        return RestrictionLevel::HighlyRestrictive;
+    }
+
+    let others: ScriptSet = text
+        .chars()
+        .filter_map(scripts)
+        .filter(|scripts| !scripts.contains("Latn"))
+        .flatten()
+        .collect();
+
+    let moderately_restrictive = others.iter().any(|other| {
+        Script::from_short_name(other).is_some_and(|script| {
+            script.is_recommended() && !matches!(script, Script::Cyrillic | Script::Greek)
+        }) && covered_by(text, &["Latn", other])
+    });
+
+    match moderately_restrictive {
+        true => RestrictionLevel::ModeratelyRestrictive,
+        false => RestrictionLevel::MinimallyRestrictive,
+    }
+}
+
+/// Non-ASCII punctuation that turns up in ordinary text.
+pub const PUNCTUATION: &str = "‘’‚“”„«»‹›–—…·•¡¿、。，！？；：「」";
+
+/// How much a string looks like text in some script, from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plausibility {
+    pub score: f64,
+    pub restriction: RestrictionLevel,
+}
+
+impl Plausibility {
+    /// Whether the text mixes scripts in a way real words rarely do, such
+    /// as Latin with Cyrillic.
+    pub fn is_suspicious(&self) -> bool {
+        self.restriction == RestrictionLevel::MinimallyRestrictive
+    }
+}
+
+/// Scores each character of `text` by whether it belongs in text at all:
+/// letters, marks and digits of any script and ordinary punctuation do,
+/// other symbols only partly, and controls, private use and unassigned code
+/// points not at all. The average is then discounted for mixing scripts.
+pub fn plausibility(text: &str) -> Plausibility {
+    let restriction = restriction_level(text);
+
+    let total: f64 = text
+        .chars()
+        .map(|c| match c {
+            '\t' | '\n' | '\r' | ' ' => 1.0,
+            '\u{FFFD}' => 0.0,
+            c if c.is_control() => 0.0,
+            c if c.script() == Script::Unknown => 0.0,
+            c if c.is_alphanumeric() || c.script() == Script::Inherited => 1.0,
+            c if c.is_ascii_punctuation() || PUNCTUATION.contains(c) => 1.0,
+            c if c.is_whitespace() => 1.0,
+            _ => 0.5,
+        })
+        .sum();
+
+    let discount = match restriction {
+        RestrictionLevel::Ascii | RestrictionLevel::SingleScript => 1.0,
+        RestrictionLevel::HighlyRestrictive => 0.9,
+        RestrictionLevel::ModeratelyRestrictive => 0.7,
+        RestrictionLevel::MinimallyRestrictive => 0.4,
+    };
+
+    let score = match text.chars().count() {
+        0 => 0.0,
+        count => discount * total / count as f64,
+    };
+
+    Plausibility { score, restriction }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_scripts() {
+        assert_eq!(restriction_level("crossword"), RestrictionLevel::Ascii);
+        assert_eq!(restriction_level("Größe"), RestrictionLevel::SingleScript);
+        assert_eq!(restriction_level("привет"), RestrictionLevel::SingleScript);
+        assert_eq!(
+            restriction_level("日本語です"),
+            RestrictionLevel::SingleScript
+        );
+        assert!(!is_mixed_script("naïve, “quoted” 123"));
+
+        let plausibility = plausibility("Größe");
+        assert_eq!(plausibility.score, 1.0);
+        assert!(!plausibility.is_suspicious());
+    }
+
+    #[test]
+    fn latin_with_han_and_kana_is_highly_restrictive() {
+        let text = "Tokyo東京とカタカナ";
+
+        assert!(is_mixed_script(text));
+        assert_eq!(restriction_level(text), RestrictionLevel::HighlyRestrictive);
+        assert_eq!(
+            restriction_level("Seoul서울"),
+            RestrictionLevel::HighlyRestrictive
+        );
+
+        let plausibility = plausibility(text);
+        assert!((plausibility.score - 0.9).abs() < 1e-9);
+        assert!(!plausibility.is_suspicious());
+    }
+
+    #[test]
+    fn latin_with_another_recommended_script_is_moderately_restrictive() {
+        assert_eq!(
+            restriction_level("Delhiदिल्ली"),
+            RestrictionLevel::ModeratelyRestrictive
+        );
+        assert!(!plausibility("Delhiदिल्ली").is_suspicious());
+    }
+
+    #[test]
+    fn latin_with_cyrillic_confusables_is_suspicious() {
+        // The second letter is Cyrillic а, which looks just like Latin a.
+        let text = "p\u{430}ypal";
+
+        assert!(is_mixed_script(text));
+        assert_eq!(
+            restriction_level(text),
+            RestrictionLevel::MinimallyRestrictive
+        );
+        assert_eq!(
+            restriction_level("αβγ abc"),
+            RestrictionLevel::MinimallyRestrictive
+        );
+
+        let plausibility = plausibility(text);
+        assert!(plausibility.is_suspicious());
+        assert!(plausibility.score < super::plausibility("paypal").score / 2.0);
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Duration, Offset, SecondsFormat, TimeZone, Timelike};
+
+/// A log timestamp before and after a signed duration correction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectedRecord<Z: TimeZone> {
+    pub original: DateTime<Z>,
+    pub corrected: DateTime<Z>,
+}
+
+impl<Z: TimeZone> CorrectedRecord<Z> {
+    pub fn new(original: DateTime<Z>, correction: Duration) -> Self {
+        let corrected = apply_correction(&original, correction);
+        Self {
+            original,
+            corrected,
+        }
+    }
+
+    /// Whether the correction moved the timestamp across one of the zone's
+    /// offset transitions.
+    pub fn crossed_transition(&self) -> bool {
+        self.original.offset().fix() != self.corrected.offset().fix()
+    }
+
+    pub fn to_iso8601(&self) -> String
+    where
+        Z::Offset: Display,
+    {
+        self.corrected.to_rfc3339_opts(SecondsFormat::Millis, false)
+    }
+}
+
+/// Shifts the instant by `correction` and re-resolves the offset in the
+/// original zone, so a correction that crosses a DST transition picks up the
+/// offset in force on the other side of it.
+pub fn apply_correction<Z: TimeZone>(time: &DateTime<Z>, correction: Duration) -> DateTime<Z> {
+    (time.to_utc() + correction).with_timezone(&time.timezone())
+}
+
+/// The signed correction for a timestamp logged with a `wrong` duration that
+/// should have been `correct`.
+pub fn correction_between(correct: Duration, wrong: Duration) -> Duration {
+    correct - wrong
+}
+
+pub fn correct_all<Z: TimeZone>(
+    records: impl IntoIterator<Item = (DateTime<Z>, Duration)>,
+) -> Vec<CorrectedRecord<Z>> {
+    records
+        .into_iter()
+        .map(|(time, correction)| CorrectedRecord::new(time, correction))
+        .collect()
+}
+
+pub fn to_iso8601_lines<Z: TimeZone>(records: &[CorrectedRecord<Z>]) -> String
+where
+    Z::Offset: Display,
+{
+    records
+        .iter()
+        .map(|record| record.to_iso8601())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// The sum of each corrected local hour weighted by its one-based line number.
+pub fn weighted_hour_sum<Z: TimeZone>(records: &[CorrectedRecord<Z>]) -> u32 {
+    records.iter().enumerate().fold(0, |sum, (i, record)| {
+        sum + (i + 1) as u32 * record.corrected.naive_local().hour()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use chrono_tz::{Europe::Berlin, Tz};
+
+    use super::*;
+
+    fn berlin(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        let local = NaiveDate::from_ymd_opt(2022, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap();
+        Berlin.from_local_datetime(&local).earliest().unwrap()
+    }
+
+    #[test]
+    fn correction_across_spring_forward() {
+        let record = CorrectedRecord::new(berlin(3, 27, 1, 30), Duration::hours(1));
+
+        assert_eq!(record.corrected, berlin(3, 27, 3, 30));
+        assert_eq!(record.corrected.offset().fix().local_minus_utc(), 7200);
+        assert!(record.crossed_transition());
+        assert_eq!(record.to_iso8601(), "2022-03-27T03:30:00.000+02:00");
+    }
+
+    #[test]
+    fn correction_across_fall_back() {
+        // Three in winter time, moved back half an hour, stays in winter
+        // time rather than landing on the earlier half past two.
+        let original = berlin(10, 30, 3, 0);
+        let record = CorrectedRecord::new(original, Duration::minutes(-30));
+
+        assert_eq!(record.corrected.naive_local().hour(), 2);
+        assert_eq!(record.corrected.offset().fix().local_minus_utc(), 3600);
+        assert!(!record.crossed_transition());
+
+        // Half past one in summer time, two hours on, is half past two in
+        // winter time.
+        let record = CorrectedRecord::new(berlin(10, 30, 1, 30), Duration::hours(2));
+        assert_eq!(record.to_iso8601(), "2022-10-30T02:30:00.000+01:00");
+        assert!(record.crossed_transition());
+    }
+
+    #[test]
+    fn correction_within_one_offset() {
+        let record = CorrectedRecord::new(berlin(7, 1, 12, 0), Duration::minutes(-90));
+
+        assert_eq!(record.corrected, berlin(7, 1, 10, 30));
+        assert!(!record.crossed_transition());
+    }
+
+    #[test]
+    fn weighted_sum_of_corrected_hours() {
+        let records = correct_all([
+            (
+                berlin(3, 27, 1, 30),
+                correction_between(Duration::hours(2), Duration::hours(1)),
+            ),
+            (berlin(7, 1, 12, 0), Duration::zero()),
+        ]);
+
+        assert_eq!(weighted_hour_sum(&records), 3 + 2 * 12);
+        assert_eq!(
+            to_iso8601_lines(&records),
+            "2022-03-27T03:30:00.000+02:00\n2022-07-01T12:00:00.000+02:00"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
+
+use super::{posix::PosixTz, tzif::TzifZone};
+
+/// Where the build script places the compiled tzdata versions, as
+/// `<ZONEINFO_DIR>/<version>/<zone>`.
+pub const ZONEINFO_DIR: &str = env!("ZONEINFO_DIR");
+
+/// Every tzdata version the build script compiled or found prebuilt, sorted
+/// by name, which for tzdata releases is also chronological.
+pub fn tzdata_versions() -> Vec<&'static str> {
+    env!("TZDATA_VERSIONS")
+        .split(',')
+        .filter(|version| !version.is_empty())
+        .collect()
+}
+
+/// How a zone differs between two tzdata versions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ZoneChange {
+    Added(String),
+    Removed(String),
+    Modified(String),
+}
+
+/// A registry of compiled tzdata versions laid out as `<root>/<version>/<zone>`.
+/// Zones are parsed on first use and cached for the lifetime of the registry.
+pub struct TzDatabase {
+    root: PathBuf,
+    versions: Vec<String>,
+    zones: RefCell<HashMap<(String, String), TzifZone>>,
+    names: RefCell<HashMap<String, Vec<String>>>,
+}
+
+impl TzDatabase {
+    pub fn open(root: impl Into<PathBuf>, versions: &[&str]) -> Result<Self> {
+        let root = root.into();
+
+        for version in versions {
+            if !root.join(version).is_dir() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("No compiled tzdata for {} in {}", version, root.display()),
+                ));
+            }
+        }
+
+        Ok(Self {
+            root,
+            versions: versions.iter().map(|v| v.to_string()).collect(),
+            zones: RefCell::new(HashMap::new()),
+            names: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn versions(&self) -> &[String] {
+        &self.versions
+    }
+
+    pub fn zone(&self, version: &str, name: &str) -> Result<TzifZone> {
+        let key = (version.to_string(), name.to_string());
+
+        if let Some(zone) = self.zones.borrow().get(&key) {
+            return Ok(zone.clone());
+        }
+
+        let path = self.root.join(version).join(name);
+
+        // Names that are not compiled zones may be raw POSIX TZ strings, which
+        // need no tzdata at all.
+        let zone = match fs::read(&path) {
+            Ok(source) => TzifZone::parse(name, &source)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => match PosixTz::parse(name) {
+                Ok(rule) => TzifZone::from_posix(name, rule),
+                Err(_) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+        self.zones.borrow_mut().insert(key, zone.clone());
+
+        Ok(zone)
+    }
+
+    /// Every zone compiled for `version`, sorted by name.
+    pub fn zone_names(&self, version: &str) -> Result<Vec<String>> {
+        if let Some(names) = self.names.borrow().get(version) {
+            return Ok(names.clone());
+        }
+
+        let directory = self.root.join(version);
+        let mut names = Vec::new();
+        collect_zone_names(&directory, &directory, &mut names)?;
+        names.sort();
+
+        self.names
+            .borrow_mut()
+            .insert(version.to_string(), names.clone());
+
+        Ok(names)
+    }
+
+    /// The UTC instant for a local time in `name` under tzdata `version`.
+    pub fn to_utc(
+        &self,
+        version: &str,
+        name: &str,
+        local: &NaiveDateTime,
+    ) -> Result<LocalResult<DateTime<Utc>>> {
+        let zone = self.zone(version, name)?;
+
+        Ok(zone
+            .from_local_datetime(local)
+            .map(|datetime| datetime.to_utc()))
+    }
+
+    /// The UTC instant for a local time in `name` under every loaded version.
+    pub fn to_utc_all(
+        &self,
+        name: &str,
+        local: &NaiveDateTime,
+    ) -> Result<Vec<(String, LocalResult<DateTime<Utc>>)>> {
+        self.versions
+            .iter()
+            .map(|version| Ok((version.clone(), self.to_utc(version, name, local)?)))
+            .collect()
+    }
+
+    /// The zones that were added, removed or given different transitions
+    /// between versions `from` and `to`.
+    pub fn diff(&self, from: &str, to: &str) -> Result<Vec<ZoneChange>> {
+        let before: BTreeSet<String> = self.zone_names(from)?.into_iter().collect();
+        let after: BTreeSet<String> = self.zone_names(to)?.into_iter().collect();
+
+        let mut changes = Vec::new();
+
+        for name in before.union(&after) {
+            match (before.contains(name), after.contains(name)) {
+                (true, false) => changes.push(ZoneChange::Removed(name.clone())),
+                (false, true) => changes.push(ZoneChange::Added(name.clone())),
+                _ => {
+                    if self.zone(from, name)? != self.zone(to, name)? {
+                        changes.push(ZoneChange::Modified(name.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+fn collect_zone_names(root: &Path, directory: &Path, names: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_zone_names(root, &path, names)?;
+        } else if is_tzif(&path)? {
+            let name = path.strip_prefix(root).unwrap().to_string_lossy();
+            names.push(name.replace(std::path::MAIN_SEPARATOR, "/"));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_tzif(path: &Path) -> Result<bool> {
+    let mut magic = [0; 4];
+    let mut file = fs::File::open(path)?;
+
+    Ok(std::io::Read::read_exact(&mut file, &mut magic).is_ok() && &magic == b"TZif")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::tz::zic;
+
+    use super::*;
+
+    const OLD: &str = "
+        R EU 1981 ma - Mar lastSu 1u 1 S
+        R EU 1996 ma - O lastSu 1u 0 -
+        Z Europe/Berlin 1 EU CE%sT
+        Z Asia/Tokyo 9 - JST
+        Z Etc/Gone 0 - GONE
+    ";
+
+    // Europe/Berlin now falls back a week earlier; Asia/Tokyo is unchanged.
+    const NEW: &str = "
+        R EU 1981 ma - Mar lastSu 1u 1 S
+        R EU 1996 ma - O Su>=18 1u 0 -
+        Z Europe/Berlin 1 EU CE%sT
+        Z Asia/Tokyo 9 - JST
+        Z Etc/New 0 - NEW
+    ";
+
+    fn database(name: &str) -> TzDatabase {
+        let root = std::env::temp_dir().join(format!("tzdb-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        for (version, text) in [("2000a", OLD), ("2000b", NEW)] {
+            let source = root.join(format!("{}.zi", version));
+            fs::create_dir_all(&root).unwrap();
+            fs::write(&source, text).unwrap();
+            zic::compile(&[source], &root.join(version)).unwrap();
+        }
+
+        TzDatabase::open(root, &["2000a", "2000b"]).unwrap()
+    }
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, min: u32) -> DateTime<Utc> {
+        local(year, month, day, hour, min).and_utc()
+    }
+
+    #[test]
+    fn ambiguous_times_have_two_instants() {
+        let database = database("ambiguous");
+
+        assert_eq!(
+            database
+                .to_utc("2000a", "Europe/Berlin", &local(2022, 10, 30, 2, 30))
+                .unwrap(),
+            LocalResult::Ambiguous(utc(2022, 10, 30, 0, 30), utc(2022, 10, 30, 1, 30))
+        );
+    }
+
+    #[test]
+    fn times_in_a_gap_have_none() {
+        let database = database("gap");
+
+        assert_eq!(
+            database
+                .to_utc("2000a", "Europe/Berlin", &local(2022, 3, 27, 2, 30))
+                .unwrap(),
+            LocalResult::None
+        );
+        assert_eq!(
+            database
+                .to_utc("2000a", "Europe/Berlin", &local(2022, 3, 27, 3, 30))
+                .unwrap(),
+            LocalResult::Single(utc(2022, 3, 27, 1, 30))
+        );
+    }
+
+    #[test]
+    fn versions_disagree_where_rules_changed() {
+        let database = database("all");
+
+        // 2022-10-23 is the fourth Sunday, after the new fall-back date.
+        assert_eq!(
+            database
+                .to_utc_all("Europe/Berlin", &local(2022, 10, 23, 12, 0))
+                .unwrap(),
+            [
+                (
+                    "2000a".to_string(),
+                    LocalResult::Single(utc(2022, 10, 23, 10, 0))
+                ),
+                (
+                    "2000b".to_string(),
+                    LocalResult::Single(utc(2022, 10, 23, 11, 0))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_lists_changed_zones() {
+        let database = database("diff");
+
+        assert_eq!(
+            database.diff("2000a", "2000b").unwrap(),
+            [
+                ZoneChange::Removed("Etc/Gone".to_string()),
+                ZoneChange::Added("Etc/New".to_string()),
+                ZoneChange::Modified("Europe/Berlin".to_string()),
+            ]
+        );
+        assert!(database.diff("2000a", "2000a").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+pub mod correction;
+pub mod database;
+pub mod posix;
+pub mod resolver;
+pub mod transitions;
+pub mod tzif;
+pub mod zic;
+pub mod zone;
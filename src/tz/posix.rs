@@ -0,0 +1,602 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+    sync::Arc,
+};
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+    Offset, TimeZone,
+};
+
+use super::tzif::LocalTimeType;
+
+/// The day a DST rule takes effect, in one of the three POSIX forms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleDate {
+    /// `Jn`: day `n` of the year from 1 to 365, never counting February 29.
+    Julian(u16),
+    /// `n`: zero-based day of the year from 0 to 365, counting February 29.
+    Ordinal(u16),
+    /// `Mm.w.d`: weekday `d` (0 is Sunday) of week `w` of month `m`, where
+    /// week 5 is the last such weekday in the month.
+    MonthWeekDay(u32, u8, u8),
+}
+
+impl RuleDate {
+    pub fn in_year(&self, year: i32) -> NaiveDate {
+        let january_first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+
+        match *self {
+            RuleDate::Julian(day) => {
+                let leap_day = NaiveDate::from_ymd_opt(year, 2, 29).is_some() && day >= 60;
+                january_first + Duration::days(day as i64 - 1 + leap_day as i64)
+            }
+            RuleDate::Ordinal(day) => january_first + Duration::days(day as i64),
+            RuleDate::MonthWeekDay(month, week, weekday) => {
+                let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+                let shift =
+                    (weekday as i64 - first.weekday().num_days_from_sunday() as i64).rem_euclid(7);
+                let mut date = first + Duration::days(shift + 7 * (week as i64 - 1));
+
+                while date.month() != month {
+                    date -= Duration::weeks(1);
+                }
+
+                date
+            }
+        }
+    }
+}
+
+/// A DST rule boundary: a date and a local time of day in seconds, which may
+/// be negative or exceed 24 hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleTime {
+    pub date: RuleDate,
+    pub time: i64,
+}
+
+impl RuleTime {
+    fn local_timestamp(&self, year: i32) -> i64 {
+        self.date
+            .in_year(year)
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+            .timestamp()
+            + self.time
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DstRule {
+    pub dst: LocalTimeType,
+    pub start: RuleTime,
+    pub end: RuleTime,
+}
+
+/// A time zone described by a POSIX TZ string such as
+/// `CET-1CEST,M3.5.0,M10.5.0/3`, as found in the footer of TZif files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosixTz {
+    pub source: String,
+    pub std: LocalTimeType,
+    pub rule: Option<DstRule>,
+}
+
+impl PosixTz {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            source,
+            rest: source,
+        };
+
+        let std_name = parser.name()?;
+        let std_utoff = -parser.offset()?;
+        let std = LocalTimeType {
+            utoff: std_utoff as i32,
+            is_dst: false,
+            abbreviation: std_name,
+        };
+
+        if parser.rest.is_empty() {
+            return Ok(Self {
+                source: source.to_string(),
+                std,
+                rule: None,
+            });
+        }
+
+        let dst_name = parser.name()?;
+        let dst_utoff = match parser.peek() {
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' => -parser.offset()?,
+            _ => std_utoff + 3600,
+        };
+        let dst = LocalTimeType {
+            utoff: dst_utoff as i32,
+            is_dst: true,
+            abbreviation: dst_name,
+        };
+
+        // Without an explicit rule POSIX leaves the dates to the
+        // implementation; like most, fall back to the current US rules.
+        let (start, end) = match parser.rest {
+            "" => (
+                RuleTime {
+                    date: RuleDate::MonthWeekDay(3, 2, 0),
+                    time: 7200,
+                },
+                RuleTime {
+                    date: RuleDate::MonthWeekDay(11, 1, 0),
+                    time: 7200,
+                },
+            ),
+            _ => {
+                parser.expect(',')?;
+                let start = parser.rule_time()?;
+                parser.expect(',')?;
+                let end = parser.rule_time()?;
+                (start, end)
+            }
+        };
+
+        if !parser.rest.is_empty() {
+            return Err(parser.error());
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            std,
+            rule: Some(DstRule { dst, start, end }),
+        })
+    }
+
+    /// The transitions in `year` as UTC timestamps and the local time type
+    /// that applies from each, in chronological order.
+    pub fn transitions_in(&self, year: i32) -> Vec<(i64, &LocalTimeType)> {
+        let Some(rule) = &self.rule else {
+            return Vec::new();
+        };
+
+        let start = rule.start.local_timestamp(year) - self.std.utoff as i64;
+        let end = rule.end.local_timestamp(year) - rule.dst.utoff as i64;
+
+        // When a year is entirely DST the end of one year coincides with the
+        // start of the next, so ends sort first and the start wins.
+        let mut transitions = vec![(end, &self.std), (start, &rule.dst)];
+        transitions.sort_by_key(|(at, local_type)| (*at, local_type.is_dst));
+        transitions
+    }
+
+    pub fn type_at_utc(&self, timestamp: i64) -> &LocalTimeType {
+        let Some(year) = DateTime::from_timestamp(timestamp, 0).map(|dt| dt.year()) else {
+            return &self.std;
+        };
+
+        let mut transitions: Vec<(i64, &LocalTimeType)> = (year - 1..=year + 1)
+            .flat_map(|year| self.transitions_in(year))
+            .collect();
+        transitions.sort_by_key(|(at, local_type)| (*at, local_type.is_dst));
+
+        transitions
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= timestamp)
+            .map_or(&self.std, |(_, local_type)| local_type)
+    }
+
+    /// Every UTC timestamp whose local time is `local`, earliest first.
+    pub fn utc_candidates(&self, local: i64) -> Vec<(i64, &LocalTimeType)> {
+        let types = match &self.rule {
+            Some(rule) => vec![&self.std, &rule.dst],
+            None => vec![&self.std],
+        };
+
+        let mut candidates: Vec<(i64, &LocalTimeType)> = types
+            .into_iter()
+            .map(|local_type| (local - local_type.utoff as i64, local_type))
+            .filter(|(utc, local_type)| self.type_at_utc(*utc) == *local_type)
+            .collect();
+        candidates.sort_by_key(|(utc, _)| *utc);
+        candidates.dedup_by_key(|(utc, _)| *utc);
+
+        candidates
+    }
+}
+
+impl FromStr for PosixTz {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        PosixTz::parse(source)
+    }
+}
+
+impl fmt::Display for PosixTz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// A reference-counted [`PosixTz`] usable as a chrono `TimeZone`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosixZone(pub Arc<PosixTz>);
+
+impl FromStr for PosixZone {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Ok(PosixZone(Arc::new(PosixTz::parse(source)?)))
+    }
+}
+
+/// The offset of a [`PosixZone`] at some instant, displayed as its
+/// abbreviation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PosixOffset {
+    zone: PosixZone,
+    local_type: LocalTimeType,
+}
+
+impl Offset for PosixOffset {
+    fn fix(&self) -> FixedOffset {
+        self.local_type.offset()
+    }
+}
+
+impl fmt::Display for PosixOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.local_type.abbreviation)
+    }
+}
+
+impl fmt::Debug for PosixOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl PosixZone {
+    fn offset(&self, local_type: &LocalTimeType) -> PosixOffset {
+        PosixOffset {
+            zone: self.clone(),
+            local_type: local_type.clone(),
+        }
+    }
+}
+
+impl TimeZone for PosixZone {
+    type Offset = PosixOffset;
+
+    fn from_offset(offset: &PosixOffset) -> Self {
+        offset.zone.clone()
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<PosixOffset> {
+        self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<PosixOffset> {
+        let candidates = self.0.utc_candidates(local.and_utc().timestamp());
+
+        match &candidates[..] {
+            [] => LocalResult::None,
+            [(_, only)] => LocalResult::Single(self.offset(only)),
+            [(_, earliest), .., (_, latest)] => {
+                LocalResult::Ambiguous(self.offset(earliest), self.offset(latest))
+            }
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> PosixOffset {
+        self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> PosixOffset {
+        self.offset(self.0.type_at_utc(utc.and_utc().timestamp()))
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                Ok(())
+            }
+            None => Err(self.error()),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let end = self.rest.find(|c| !predicate(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    /// An abbreviation, either alphabetic or quoted in angle brackets.
+    fn name(&mut self) -> Result<String> {
+        let name = if self.peek() == Some('<') {
+            self.expect('<')?;
+            let name = self.take_while(|c| c != '>').to_string();
+            self.expect('>')?;
+            name
+        } else {
+            self.take_while(|c| c.is_ascii_alphabetic()).to_string()
+        };
+
+        match name.len() {
+            3.. => Ok(name),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64> {
+        self.take_while(|c| c.is_ascii_digit())
+            .parse()
+            .map_err(|_| self.error())
+    }
+
+    /// `[+-]hh[:mm[:ss]]` in seconds, with at most `max_hours` hours.
+    fn duration(&mut self, max_hours: i64) -> Result<i64> {
+        let sign = match self.peek() {
+            Some('-') => -1,
+            _ => 1,
+        };
+        if matches!(self.peek(), Some('+' | '-')) {
+            self.rest = &self.rest[1..];
+        }
+
+        let mut seconds = match self.number()? {
+            hours @ 0.. if hours <= max_hours => hours * 3600,
+            _ => return Err(self.error()),
+        };
+        for unit in [60, 1] {
+            if self.peek() != Some(':') {
+                break;
+            }
+            self.expect(':')?;
+            match self.number()? {
+                part @ 0..=59 => seconds += part * unit,
+                _ => return Err(self.error()),
+            }
+        }
+
+        Ok(sign * seconds)
+    }
+
+    /// A POSIX offset, which is the amount added to local time to get UTC.
+    fn offset(&mut self) -> Result<i64> {
+        let offset = self.duration(24)?;
+
+        match offset.abs() <= 24 * 3600 {
+            true => Ok(offset),
+            false => Err(self.error()),
+        }
+    }
+
+    fn rule_time(&mut self) -> Result<RuleTime> {
+        let date = match self.peek() {
+            Some('J') => {
+                self.expect('J')?;
+                match self.number()? {
+                    day @ 1..=365 => RuleDate::Julian(day as u16),
+                    _ => return Err(self.error()),
+                }
+            }
+            Some('M') => {
+                self.expect('M')?;
+                let month = self.number()?;
+                self.expect('.')?;
+                let week = self.number()?;
+                self.expect('.')?;
+                let weekday = self.number()?;
+
+                if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+                    return Err(self.error());
+                }
+                RuleDate::MonthWeekDay(month as u32, week as u8, weekday as u8)
+            }
+            _ => match self.number()? {
+                day @ 0..=365 => RuleDate::Ordinal(day as u16),
+                _ => return Err(self.error()),
+            },
+        };
+
+        let time = match self.peek() {
+            Some('/') => {
+                self.expect('/')?;
+                match self.duration(167)? {
+                    time if time.abs() <= 167 * 3600 => time,
+                    _ => return Err(self.error()),
+                }
+            }
+            _ => 7200,
+        };
+
+        Ok(RuleTime { date, time })
+    }
+
+    fn error(&self) -> Error {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid POSIX TZ string {:?} at offset {}",
+                self.source,
+                self.source.len() - self.rest.len()
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn transitions(tz: &PosixTz, year: i32) -> Vec<(i64, &str)> {
+        tz.transitions_in(year)
+            .into_iter()
+            .map(|(at, local_type)| (at, local_type.abbreviation.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn julian_days_skip_february_29() {
+        assert_eq!(RuleDate::Julian(59).in_year(2024), date(2024, 2, 28));
+        assert_eq!(RuleDate::Julian(60).in_year(2024), date(2024, 3, 1));
+        assert_eq!(RuleDate::Julian(60).in_year(2023), date(2023, 3, 1));
+        assert_eq!(RuleDate::Julian(365).in_year(2024), date(2024, 12, 31));
+    }
+
+    #[test]
+    fn ordinal_days_count_february_29() {
+        assert_eq!(RuleDate::Ordinal(0).in_year(2024), date(2024, 1, 1));
+        assert_eq!(RuleDate::Ordinal(59).in_year(2024), date(2024, 2, 29));
+        assert_eq!(RuleDate::Ordinal(59).in_year(2023), date(2023, 3, 1));
+        assert_eq!(RuleDate::Ordinal(365).in_year(2024), date(2024, 12, 31));
+    }
+
+    #[test]
+    fn week_5_is_the_last_weekday_of_the_month() {
+        assert_eq!(
+            RuleDate::MonthWeekDay(3, 5, 0).in_year(2024),
+            date(2024, 3, 31)
+        );
+        assert_eq!(
+            RuleDate::MonthWeekDay(10, 5, 0).in_year(2024),
+            date(2024, 10, 27)
+        );
+        // February 2023 has only four Wednesdays.
+        assert_eq!(
+            RuleDate::MonthWeekDay(2, 5, 3).in_year(2023),
+            date(2023, 2, 22)
+        );
+        assert_eq!(
+            RuleDate::MonthWeekDay(3, 1, 0).in_year(2024),
+            date(2024, 3, 3)
+        );
+    }
+
+    #[test]
+    fn central_europe() {
+        let tz = PosixTz::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+
+        assert_eq!(tz.std.utoff, 3600);
+        assert_eq!(tz.rule.as_ref().unwrap().dst.utoff, 7200);
+        assert_eq!(
+            transitions(&tz, 2024),
+            vec![(1711846800, "CEST"), (1729990800, "CET")]
+        );
+        assert_eq!(tz.to_string(), "CET-1CEST,M3.5.0,M10.5.0/3");
+    }
+
+    #[test]
+    fn southern_hemisphere() {
+        let tz = PosixTz::parse("NZST-12NZDT,M9.5.0,M4.1.0/3").unwrap();
+
+        assert_eq!(
+            transitions(&tz, 2024),
+            vec![(1712412000, "NZST"), (1727532000, "NZDT")]
+        );
+        assert_eq!(tz.type_at_utc(1704067200).abbreviation, "NZDT");
+        assert_eq!(tz.type_at_utc(1719792000).abbreviation, "NZST");
+    }
+
+    #[test]
+    fn quoted_names_and_negative_times() {
+        let tz = PosixTz::parse("<-02>2<-01>,M3.5.0/-1,M10.5.0/0").unwrap();
+
+        assert_eq!(tz.std.abbreviation, "-02");
+        assert_eq!(tz.std.utoff, -7200);
+        assert_eq!(tz.rule.as_ref().unwrap().dst.utoff, -3600);
+        assert_eq!(
+            transitions(&tz, 2024),
+            vec![(1711846800, "-01"), (1729990800, "-02")]
+        );
+    }
+
+    #[test]
+    fn times_past_24_hours() {
+        let tz = PosixTz::parse("IST-2IDT,M3.4.4/26,M10.5.0").unwrap();
+
+        assert_eq!(
+            transitions(&tz, 2024),
+            vec![(1711670400, "IDT"), (1729983600, "IST")]
+        );
+    }
+
+    #[test]
+    fn without_dst() {
+        let tz = PosixTz::parse("<+03>-3").unwrap();
+
+        assert_eq!(tz.std.abbreviation, "+03");
+        assert_eq!(tz.std.utoff, 3 * 3600);
+        assert!(tz.rule.is_none());
+        assert!(tz.transitions_in(2024).is_empty());
+        assert_eq!(tz.type_at_utc(0).abbreviation, "+03");
+    }
+
+    #[test]
+    fn local_times_in_gaps_and_overlaps() {
+        let zone: PosixZone = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+        let local = |day, hour| date(2024, 3, day).and_hms_opt(hour, 30, 0).unwrap();
+
+        assert!(matches!(
+            zone.offset_from_local_datetime(&local(31, 2)),
+            LocalResult::None
+        ));
+        assert!(matches!(
+            zone.offset_from_local_datetime(&local(31, 3)),
+            LocalResult::Single(offset) if offset.to_string() == "CEST"
+        ));
+
+        let october = date(2024, 10, 27).and_hms_opt(2, 30, 0).unwrap();
+        match zone.offset_from_local_datetime(&october) {
+            LocalResult::Ambiguous(earliest, latest) => {
+                assert_eq!(earliest.to_string(), "CEST");
+                assert_eq!(latest.to_string(), "CET");
+            }
+            other => panic!("Expected an ambiguous time, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_strings() {
+        for source in [
+            "",
+            "CE-1",
+            "CET",
+            "CET-25",
+            "<+03-3",
+            "CET-1CEST,M13.1.0,M10.5.0",
+            "CET-1CEST,M3.6.0,M10.5.0",
+            "CET-1CEST,J0,J365",
+            "CET-1CEST,366,0",
+            "CET-1CEST,M3.5.0/168,M10.5.0",
+            "CET-1CEST,M3.5.0/2:60,M10.5.0",
+            "CET-1CEST,M3.5.0/9999999999999999,M10.5.0",
+            "<ABC>9999999999999999",
+            "<ABC>1:9999999999999999",
+            "<ABC>-99999999999999999999",
+            "CET-1CEST,M3.5.0",
+            "CET-1CEST,M3.5.0,M10.5.0x",
+        ] {
+            assert!(PosixTz::parse(source).is_err(), "{:?}", source);
+        }
+    }
+}
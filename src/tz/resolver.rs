@@ -0,0 +1,137 @@
+use chrono::{DateTime, FixedOffset, Offset, TimeZone};
+
+/// The outcome of matching a timestamp and its recorded offset against a set
+/// of candidate zones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution<Z: TimeZone> {
+    Unique(DateTime<Z>),
+    Ambiguous(Vec<DateTime<Z>>),
+    Unmatched,
+}
+
+impl<Z: TimeZone> Resolution<Z> {
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(self, Resolution::Ambiguous(_))
+    }
+
+    pub fn is_unmatched(&self) -> bool {
+        matches!(self, Resolution::Unmatched)
+    }
+
+    /// Every consistent zone, in the order the candidates were given.
+    pub fn matches(&self) -> &[DateTime<Z>] {
+        match self {
+            Resolution::Unique(datetime) => std::slice::from_ref(datetime),
+            Resolution::Ambiguous(datetimes) => datetimes,
+            Resolution::Unmatched => &[],
+        }
+    }
+
+    /// The first consistent zone, which breaks ties by candidate order.
+    pub fn preferred(&self) -> Option<&DateTime<Z>> {
+        self.matches().first()
+    }
+}
+
+/// Works out which of a set of candidate zones could have produced a
+/// timestamp written with an explicit UTC offset.
+pub struct ZoneResolver<Z: TimeZone> {
+    candidates: Vec<Z>,
+}
+
+impl<Z: TimeZone> ZoneResolver<Z> {
+    pub fn new(candidates: impl IntoIterator<Item = Z>) -> Self {
+        Self {
+            candidates: candidates.into_iter().collect(),
+        }
+    }
+
+    pub fn candidates(&self) -> &[Z] {
+        &self.candidates
+    }
+
+    /// A zone is consistent with the record if, at the instant the record
+    /// describes, the zone's offset is exactly the one that was written down.
+    pub fn resolve(&self, record: &DateTime<FixedOffset>) -> Resolution<Z> {
+        let mut matches: Vec<DateTime<Z>> = self
+            .candidates
+            .iter()
+            .map(|zone| record.with_timezone(zone))
+            .filter(|datetime| datetime.offset().fix() == *record.offset())
+            .collect();
+
+        match matches.len() {
+            0 => Resolution::Unmatched,
+            1 => Resolution::Unique(matches.remove(0)),
+            _ => Resolution::Ambiguous(matches),
+        }
+    }
+
+    pub fn resolve_all<'a>(
+        &'a self,
+        records: impl IntoIterator<Item = &'a DateTime<FixedOffset>> + 'a,
+    ) -> impl Iterator<Item = Resolution<Z>> + 'a {
+        records.into_iter().map(|record| self.resolve(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::{America::Halifax, America::Santiago, Europe::Berlin, Tz};
+
+    use super::*;
+
+    fn resolver() -> ZoneResolver<Tz> {
+        ZoneResolver::new([Santiago, Halifax, Berlin])
+    }
+
+    fn record(timestamp: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap()
+    }
+
+    #[test]
+    fn unique_match() {
+        let resolution = resolver().resolve(&record("2022-07-01T12:00:00+02:00"));
+
+        assert!(
+            matches!(&resolution, Resolution::Unique(datetime) if datetime.timezone() == Berlin)
+        );
+        assert_eq!(resolution.preferred().unwrap().timezone(), Berlin);
+    }
+
+    #[test]
+    fn ambiguous_match_keeps_candidate_order() {
+        // In October both are on summer time, three hours behind UTC.
+        let resolution = resolver().resolve(&record("2022-10-15T08:00:00-03:00"));
+
+        assert!(resolution.is_ambiguous());
+        let zones: Vec<Tz> = resolution
+            .matches()
+            .iter()
+            .map(|datetime| datetime.timezone())
+            .collect();
+        assert_eq!(zones, vec![Santiago, Halifax]);
+        assert_eq!(resolution.preferred().unwrap().timezone(), Santiago);
+    }
+
+    #[test]
+    fn unmatched_offset() {
+        let resolution = resolver().resolve(&record("2022-07-01T12:00:00+05:30"));
+
+        assert!(resolution.is_unmatched());
+        assert!(resolution.matches().is_empty());
+        assert_eq!(resolution.preferred(), None);
+    }
+
+    #[test]
+    fn resolves_each_record() {
+        let records = [
+            record("2022-07-01T12:00:00+02:00"),
+            record("2022-07-01T12:00:00+05:30"),
+        ];
+        let resolutions: Vec<_> = resolver().resolve_all(&records).collect();
+
+        assert!(matches!(resolutions[0], Resolution::Unique(_)));
+        assert!(resolutions[1].is_unmatched());
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Result},
+};
+
+use chrono::{DateTime, Datelike, FixedOffset, SecondsFormat, Utc};
+
+use super::{
+    database::TzDatabase,
+    tzif::{LocalTimeType, TzifZone},
+};
+
+/// A change of local time type at a UTC instant.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Transition {
+    pub at: DateTime<Utc>,
+    pub before: LocalTimeType,
+    pub after: LocalTimeType,
+}
+
+/// How many years past the current one, or past the start of the range if
+/// that is later, the footer rule is followed when no end year is given.
+const FOOTER_YEARS: i32 = 5;
+
+/// Every transition of `zone` whose UTC year lies in `from..=to`, where a
+/// missing bound is unbounded, except that transitions from the footer rule
+/// stop [`FOOTER_YEARS`] after the current year or `from`, whichever is later,
+/// when `to` is not given.
+pub fn transitions(zone: &TzifZone, from: Option<i32>, to: Option<i32>) -> Vec<Transition> {
+    let in_range = |at: &DateTime<Utc>| {
+        from.is_none_or(|from| at.year() >= from) && to.is_none_or(|to| at.year() <= to)
+    };
+
+    let mut before = zone.initial_type();
+    let mut result = Vec::new();
+
+    for (timestamp, after) in zone.transitions() {
+        let Some(at) = DateTime::from_timestamp(timestamp, 0) else {
+            continue;
+        };
+
+        if in_range(&at) {
+            result.push(Transition {
+                at,
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+
+        before = after;
+    }
+
+    // Past the last explicit transition the footer rule takes over. Its
+    // transitions are generated for a year either side of the range, since a
+    // rule's local year need not be the UTC year of the instant it changes.
+    if let Some(footer) = zone.footer() {
+        let last = zone.transitions().last().map(|(at, _)| at);
+        let horizon = Utc::now().year().max(from.unwrap_or(i32::MIN));
+        let last_year = to.unwrap_or(horizon.saturating_add(FOOTER_YEARS));
+        let first_year = last
+            .and_then(|at| DateTime::from_timestamp(at, 0))
+            .map_or(from.unwrap_or(1970), |at| {
+                at.year().max(from.unwrap_or(i32::MIN))
+            });
+
+        let mut generated: Vec<(i64, &LocalTimeType)> = (first_year - 1..=last_year + 1)
+            .flat_map(|year| footer.transitions_in(year))
+            .filter(|(timestamp, _)| last.is_none_or(|last| *timestamp > last))
+            .collect();
+        generated.sort_by_key(|(timestamp, local_type)| (*timestamp, local_type.is_dst));
+        generated.dedup_by_key(|(timestamp, _)| *timestamp);
+
+        for (timestamp, after) in generated {
+            let Some(at) = DateTime::from_timestamp(timestamp, 0) else {
+                continue;
+            };
+
+            if after == before || at.year() > last_year {
+                continue;
+            }
+
+            if in_range(&at) {
+                result.push(Transition {
+                    at,
+                    before: before.clone(),
+                    after: after.clone(),
+                });
+            }
+
+            before = after;
+        }
+    }
+
+    result
+}
+
+/// Lists the transitions of `zone` in each of `versions`, one per line.
+/// Transitions missing from some of the versions are marked with `*` and
+/// followed by the versions that have them.
+pub fn report(
+    database: &TzDatabase,
+    zone: &str,
+    versions: &[&str],
+    from: Option<i32>,
+    to: Option<i32>,
+) -> Result<String> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if to < from {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("--to {} is before --from {}", to, from),
+            ));
+        }
+    }
+
+    let mut seen: BTreeMap<Transition, Vec<&str>> = BTreeMap::new();
+
+    for version in versions {
+        for transition in transitions(&database.zone(version, zone)?, from, to) {
+            seen.entry(transition).or_default().push(version);
+        }
+    }
+
+    let mut lines = vec![format!("{} ({})", zone, versions.join(", "))];
+
+    for (transition, found_in) in seen {
+        let line = format!(
+            "{}  {} -> {}",
+            transition.at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            describe(&transition.before),
+            describe(&transition.after)
+        );
+
+        if found_in.len() == versions.len() {
+            lines.push(format!("  {}", line));
+        } else {
+            lines.push(format!("* {}  [{}]", line, found_in.join(", ")));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn describe(local_type: &LocalTimeType) -> String {
+    let offset = FixedOffset::east_opt(local_type.utoff).unwrap();
+
+    format!(
+        "{} {:<6} {}",
+        offset,
+        local_type.abbreviation,
+        if local_type.is_dst { "DST" } else { "STD" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::tz::zic;
+
+    use super::*;
+
+    /// Berlin with explicit transitions up to 2037 and the EU rule as its
+    /// footer after.
+    const BERLIN: &str = "
+        R EU 1981 ma - Mar lastSu 1u 1 S
+        R EU 1996 ma - O lastSu 1u 0 -
+        Z Europe/Berlin 1 EU CE%sT
+    ";
+
+    fn database(name: &str) -> TzDatabase {
+        let root =
+            std::env::temp_dir().join(format!("transitions-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let source = root.join("berlin.zi");
+        fs::write(&source, BERLIN).unwrap();
+        zic::compile(&[source], &root.join("2000a")).unwrap();
+
+        TzDatabase::open(root, &["2000a"]).unwrap()
+    }
+
+    fn years(transitions: &[Transition]) -> Vec<i32> {
+        transitions
+            .iter()
+            .map(|transition| transition.at.year())
+            .collect()
+    }
+
+    #[test]
+    fn footer_transitions_follow_the_explicit_ones() {
+        let zone = database("footer").zone("2000a", "Europe/Berlin").unwrap();
+        let last = zone.transitions().last().unwrap().0;
+        assert_eq!(DateTime::from_timestamp(last, 0).unwrap().year(), 2037);
+
+        let transitions = transitions(&zone, Some(2040), Some(2040));
+        assert_eq!(
+            transitions
+                .iter()
+                .map(|transition| transition.at.to_rfc3339())
+                .collect::<Vec<_>>(),
+            ["2040-03-25T01:00:00+00:00", "2040-10-28T01:00:00+00:00"]
+        );
+        assert!(transitions[0].after.is_dst && !transitions[1].after.is_dst);
+        assert_eq!(transitions[0].before, transitions[1].after);
+    }
+
+    #[test]
+    fn footer_transitions_without_an_end_follow_the_start() {
+        let zone = database("horizon").zone("2000a", "Europe/Berlin").unwrap();
+        let start = Utc::now().year().max(2100);
+
+        assert_eq!(
+            years(&transitions(&zone, Some(start), None)),
+            (start..=start + FOOTER_YEARS)
+                .flat_map(|year| [year, year])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn empty_ranges() {
+        let database = database("empty");
+        let zone = database.zone("2000a", "Europe/Berlin").unwrap();
+
+        assert!(transitions(&zone, Some(2041), Some(2040)).is_empty());
+        assert!(transitions(&zone, None, Some(1900)).is_empty());
+        assert!(report(
+            &database,
+            "Europe/Berlin",
+            &["2000a"],
+            Some(2041),
+            Some(2040)
+        )
+        .is_err());
+        assert_eq!(
+            report(
+                &database,
+                "Europe/Berlin",
+                &["2000a"],
+                Some(2040),
+                Some(2040)
+            )
+            .unwrap()
+            .lines()
+            .count(),
+            3
+        );
+    }
+}
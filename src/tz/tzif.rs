@@ -0,0 +1,532 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    sync::Arc,
+};
+
+use chrono::{FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+
+use super::posix::PosixTz;
+
+/// The widest UTC offset a zone may use, with some slack, in seconds.
+const MAX_OFFSET: i64 = 26 * 60 * 60;
+
+/// A local time type from a TZif file: the UTC offset, whether it is daylight
+/// saving time, and its abbreviation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LocalTimeType {
+    pub utoff: i32,
+    pub is_dst: bool,
+    pub abbreviation: String,
+}
+
+impl LocalTimeType {
+    pub fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utoff).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ZoneData {
+    name: String,
+    transitions: Vec<i64>,
+    transition_types: Vec<usize>,
+    local_types: Vec<LocalTimeType>,
+    footer: Option<PosixTz>,
+}
+
+/// A time zone read from a TZif file (RFC 8536), versions 1 to 4. Times after
+/// the last explicit transition follow the POSIX TZ string in the footer.
+///
+/// The zone is reference-counted so that it can be cloned cheaply into every
+/// `DateTime` that uses it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TzifZone(Arc<ZoneData>);
+
+impl TzifZone {
+    pub fn parse(name: &str, source: &[u8]) -> Result<Self> {
+        let mut reader = Reader {
+            source,
+            position: 0,
+        };
+        let header = reader.header()?;
+
+        // Version 2+ files repeat the data with 64-bit transition times after
+        // the version 1 block, followed by a POSIX TZ string footer.
+        let (header, time_size) = if header.version >= b'2' {
+            reader.skip(header.block_len(4))?;
+            (reader.header()?, 8)
+        } else {
+            (header, 4)
+        };
+
+        let transitions = (0..header.timecnt)
+            .map(|_| reader.signed(time_size))
+            .collect::<Result<Vec<i64>>>()?;
+        let transition_types = (0..header.timecnt)
+            .map(|_| Ok(reader.bytes(1)?[0] as usize))
+            .collect::<Result<Vec<usize>>>()?;
+
+        let raw_types = (0..header.typecnt)
+            .map(|_| {
+                let utoff = reader.signed(4)? as i32;
+                let flags = reader.bytes(2)?;
+                Ok((utoff, flags[0] != 0, flags[1] as usize))
+            })
+            .collect::<Result<Vec<(i32, bool, usize)>>>()?;
+        let abbreviations = reader.bytes(header.charcnt)?;
+
+        let local_types = raw_types
+            .into_iter()
+            .map(|(utoff, is_dst, index)| {
+                let rest = abbreviations
+                    .get(index..)
+                    .ok_or_else(|| invalid("abbreviation index"))?;
+                let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+                Ok(LocalTimeType {
+                    utoff,
+                    is_dst,
+                    abbreviation: String::from_utf8_lossy(&rest[..end]).into_owned(),
+                })
+            })
+            .collect::<Result<Vec<LocalTimeType>>>()?;
+
+        if transition_types.iter().any(|&t| t >= local_types.len()) || local_types.is_empty() {
+            return Err(invalid("transition type out of range"));
+        }
+
+        reader.skip(header.leapcnt * (time_size + 4) + header.isstdcnt + header.isutcnt)?;
+
+        let footer = match header.version >= b'2' {
+            true => reader
+                .footer()?
+                .map(|footer| PosixTz::parse(&footer))
+                .transpose()?,
+            false => None,
+        };
+
+        Ok(Self(Arc::new(ZoneData {
+            name: name.to_string(),
+            transitions,
+            transition_types,
+            local_types,
+            footer,
+        })))
+    }
+
+    /// A zone with no explicit transitions, described entirely by a POSIX TZ
+    /// string.
+    pub fn from_posix(name: &str, rule: PosixTz) -> Self {
+        Self(Arc::new(ZoneData {
+            name: name.to_string(),
+            transitions: Vec::new(),
+            transition_types: Vec::new(),
+            local_types: vec![rule.std.clone()],
+            footer: Some(rule),
+        }))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// The POSIX TZ rule that describes times after the last transition.
+    pub fn footer(&self) -> Option<&PosixTz> {
+        self.0.footer.as_ref()
+    }
+
+    pub fn local_types(&self) -> &[LocalTimeType] {
+        &self.0.local_types
+    }
+
+    /// Every explicit transition as a UTC timestamp and the local time type
+    /// that applies from that instant.
+    pub fn transitions(&self) -> impl Iterator<Item = (i64, &LocalTimeType)> {
+        self.0
+            .transitions
+            .iter()
+            .zip(&self.0.transition_types)
+            .map(|(at, index)| (*at, &self.0.local_types[*index]))
+    }
+
+    /// The local time type in force before the first transition.
+    pub fn initial_type(&self) -> &LocalTimeType {
+        &self.0.local_types[0]
+    }
+
+    pub fn type_at_utc(&self, timestamp: i64) -> &LocalTimeType {
+        let index = self.0.transitions.partition_point(|&at| at <= timestamp);
+
+        match &self.0.footer {
+            Some(footer) if index == self.0.transitions.len() => footer.type_at_utc(timestamp),
+            _ => self.period_type(index),
+        }
+    }
+
+    /// Every UTC timestamp whose local time is `local`, earliest first.
+    pub fn utc_candidates(&self, local: i64) -> Vec<(i64, &LocalTimeType)> {
+        let transitions = &self.0.transitions;
+        let first = transitions.partition_point(|&at| at < local - MAX_OFFSET);
+
+        let mut candidates = Vec::new();
+
+        for period in first..=transitions.len() {
+            let start = match period {
+                0 => i64::MIN,
+                p => transitions[p - 1],
+            };
+            if start > local + MAX_OFFSET {
+                break;
+            }
+            let end = transitions.get(period).copied().unwrap_or(i64::MAX);
+
+            match &self.0.footer {
+                Some(footer) if period == transitions.len() => candidates.extend(
+                    footer
+                        .utc_candidates(local)
+                        .into_iter()
+                        .filter(|(utc, _)| start <= *utc),
+                ),
+                _ => {
+                    let local_type = self.period_type(period);
+                    let utc = local - local_type.utoff as i64;
+                    if start <= utc && utc < end {
+                        candidates.push((utc, local_type));
+                    }
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// The local time type for the span after `period` transitions.
+    fn period_type(&self, period: usize) -> &LocalTimeType {
+        match period {
+            0 => self.initial_type(),
+            p => &self.0.local_types[self.0.transition_types[p - 1]],
+        }
+    }
+
+    fn offset(&self, local_type: &LocalTimeType) -> TzifOffset {
+        TzifOffset {
+            zone: self.clone(),
+            local_type: local_type.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for TzifZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.name)
+    }
+}
+
+/// The offset of a [`TzifZone`] at some instant, displayed as its abbreviation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TzifOffset {
+    zone: TzifZone,
+    local_type: LocalTimeType,
+}
+
+impl TzifOffset {
+    pub fn local_type(&self) -> &LocalTimeType {
+        &self.local_type
+    }
+}
+
+impl Offset for TzifOffset {
+    fn fix(&self) -> FixedOffset {
+        self.local_type.offset()
+    }
+}
+
+impl fmt::Display for TzifOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.local_type.abbreviation)
+    }
+}
+
+impl fmt::Debug for TzifOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl TimeZone for TzifZone {
+    type Offset = TzifOffset;
+
+    fn from_offset(offset: &TzifOffset) -> Self {
+        offset.zone.clone()
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<TzifOffset> {
+        self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<TzifOffset> {
+        let candidates = self.utc_candidates(local.and_utc().timestamp());
+
+        match &candidates[..] {
+            [] => LocalResult::None,
+            [(_, only)] => LocalResult::Single(self.offset(only)),
+            [(_, earliest), .., (_, latest)] => {
+                LocalResult::Ambiguous(self.offset(earliest), self.offset(latest))
+            }
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> TzifOffset {
+        self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> TzifOffset {
+        self.offset(self.type_at_utc(utc.and_utc().timestamp()))
+    }
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Header {
+    fn block_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+struct Reader<'a> {
+    source: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .source
+            .get(self.position..self.position + len)
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn signed(&mut self, size: usize) -> Result<i64> {
+        let bytes = self.bytes(size)?;
+        Ok(match size {
+            4 => i32::from_be_bytes(bytes.try_into().unwrap()) as i64,
+            _ => i64::from_be_bytes(bytes.try_into().unwrap()),
+        })
+    }
+
+    fn count(&mut self) -> Result<usize> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    fn header(&mut self) -> Result<Header> {
+        if self.bytes(4)? != b"TZif" {
+            return Err(invalid("missing TZif magic"));
+        }
+
+        let version = match self.bytes(1)?[0] {
+            0 => b'1',
+            version => version,
+        };
+        self.skip(15)?;
+
+        Ok(Header {
+            version,
+            isutcnt: self.count()?,
+            isstdcnt: self.count()?,
+            leapcnt: self.count()?,
+            timecnt: self.count()?,
+            typecnt: self.count()?,
+            charcnt: self.count()?,
+        })
+    }
+
+    fn footer(&mut self) -> Result<Option<String>> {
+        let rest = &self.source[self.position..];
+
+        match rest {
+            [b'\n', footer @ ..] => {
+                let end = footer
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .ok_or_else(|| invalid("unterminated footer"))?;
+                let footer = String::from_utf8_lossy(&footer[..end]).into_owned();
+                Ok((!footer.is_empty()).then_some(footer))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Invalid TZif data: {}", message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header and data block with the given transitions, types and
+    /// abbreviations, and no leap seconds or indicators.
+    fn block(
+        version: u8,
+        time_size: usize,
+        transitions: &[(i64, u8)],
+        types: &[(i32, bool, u8)],
+        chars: &[u8],
+    ) -> Vec<u8> {
+        let mut data = b"TZif".to_vec();
+        data.push(version);
+        data.extend([0; 15]);
+
+        for count in [0, 0, 0, transitions.len(), types.len(), chars.len()] {
+            data.extend((count as u32).to_be_bytes());
+        }
+        for (at, _) in transitions {
+            match time_size {
+                4 => data.extend((*at as i32).to_be_bytes()),
+                _ => data.extend(at.to_be_bytes()),
+            }
+        }
+        data.extend(transitions.iter().map(|(_, index)| index));
+        for (utoff, is_dst, index) in types {
+            data.extend(utoff.to_be_bytes());
+            data.extend([*is_dst as u8, *index]);
+        }
+        data.extend(chars);
+
+        data
+    }
+
+    const NEW_YORK_TYPES: [(i32, bool, u8); 3] =
+        [(-17762, false, 0), (-14400, true, 4), (-18000, false, 8)];
+    const NEW_YORK_CHARS: &[u8] = b"LMT\0EDT\0EST\0";
+
+    /// 1970-04-26 07:00 and 1970-10-25 06:00 UTC.
+    const NEW_YORK_1970: [(i64, u8); 2] = [(9_961_200, 1), (25_682_400, 2)];
+
+    #[test]
+    fn version_1() {
+        let source = block(0, 4, &NEW_YORK_1970, &NEW_YORK_TYPES, NEW_YORK_CHARS);
+        let zone = TzifZone::parse("America/New_York", &source).unwrap();
+
+        assert_eq!(zone.name(), "America/New_York");
+        assert_eq!(zone.footer(), None);
+        assert_eq!(zone.initial_type().abbreviation, "LMT");
+        assert_eq!(
+            zone.transitions()
+                .map(|(at, local_type)| (at, local_type.abbreviation.as_str()))
+                .collect::<Vec<_>>(),
+            [(9_961_200, "EDT"), (25_682_400, "EST")]
+        );
+
+        assert_eq!(zone.type_at_utc(0).abbreviation, "LMT");
+        assert_eq!(zone.type_at_utc(9_961_199).abbreviation, "LMT");
+        assert_eq!(zone.type_at_utc(9_961_200).abbreviation, "EDT");
+        assert_eq!(zone.type_at_utc(i64::MAX).abbreviation, "EST");
+
+        // 01:30 local on 25 October 1970 happened twice, first in EDT.
+        let local = 25_682_400 - 14_400 - 1800;
+        let candidates: Vec<i64> = zone
+            .utc_candidates(local)
+            .iter()
+            .map(|(utc, _)| *utc)
+            .collect();
+        assert_eq!(candidates, [local + 14_400, local + 18_000]);
+    }
+
+    #[test]
+    fn version_2_with_footer() {
+        let types = [(3208, false, 0), (3600, false, 4), (7200, true, 8)];
+        let chars = b"LMT\0CET\0CEST\0";
+        // 1893-03-31 23:06:32 UTC, before 32-bit times begin, then the
+        // start and end of daylight saving time in 2037.
+        let transitions = [(-2_422_054_408, 1), (2_121_901_200, 2), (2_140_045_200, 1)];
+
+        let mut source = block(b'2', 4, &[], &types[..1], b"LMT\0");
+        source.extend(block(b'2', 8, &transitions, &types, chars));
+        source.extend(b"\nCET-1CEST,M3.5.0,M10.5.0/3\n");
+
+        let zone = TzifZone::parse("Europe/Berlin", &source).unwrap();
+
+        assert_eq!(zone.transitions().count(), 3);
+        assert_eq!(zone.local_types().len(), 3);
+        assert_eq!(
+            zone.footer().map(|footer| footer.source.as_str()),
+            Some("CET-1CEST,M3.5.0,M10.5.0/3")
+        );
+
+        assert_eq!(zone.type_at_utc(-2_422_054_409).abbreviation, "LMT");
+        assert_eq!(zone.type_at_utc(0).abbreviation, "CET");
+        assert_eq!(zone.type_at_utc(2_121_901_200).abbreviation, "CEST");
+
+        // 2040-07-01 and 2040-12-01 lie past the last transition, where the
+        // footer applies.
+        assert_eq!(zone.type_at_utc(2_224_713_600).abbreviation, "CEST");
+        assert_eq!(zone.type_at_utc(2_237_932_800).abbreviation, "CET");
+
+        // 2040-03-25 02:30 local time does not exist.
+        let gap = 2_216_255_400;
+        assert!(zone.utc_candidates(gap).is_empty());
+    }
+
+    #[test]
+    fn version_3_and_an_empty_footer() {
+        let types = [(-14400, false, 0)];
+
+        let mut source = block(b'3', 4, &[], &types, b"-04\0");
+        source.extend(block(b'3', 8, &[], &types, b"-04\0"));
+        source.extend(b"\n<-04>4<-03>,M9.1.6/24,M4.1.6/24\n");
+        let zone = TzifZone::parse("America/Santiago", &source).unwrap();
+        assert!(zone.footer().is_some_and(|footer| footer.rule.is_some()));
+
+        let mut source = block(b'2', 4, &[], &types, b"-04\0");
+        source.extend(block(b'2', 8, &[], &types, b"-04\0"));
+        source.extend(b"\n\n");
+        let zone = TzifZone::parse("Etc/Unknown", &source).unwrap();
+        assert_eq!(zone.footer(), None);
+        assert_eq!(zone.type_at_utc(0).abbreviation, "-04");
+    }
+
+    #[test]
+    fn invalid_data() {
+        let valid = block(0, 4, &NEW_YORK_1970, &NEW_YORK_TYPES, NEW_YORK_CHARS);
+
+        assert!(TzifZone::parse("Bad/Magic", &valid[1..]).is_err());
+        assert!(TzifZone::parse("Bad/Truncated", &valid[..valid.len() - 1]).is_err());
+
+        let out_of_range = block(0, 4, &[(0, 3)], &NEW_YORK_TYPES, NEW_YORK_CHARS);
+        assert!(TzifZone::parse("Bad/Type", &out_of_range).is_err());
+
+        let no_types = block(0, 4, &[], &[], b"");
+        assert!(TzifZone::parse("Bad/Types", &no_types).is_err());
+
+        let mut unterminated = block(b'2', 4, &[], &NEW_YORK_TYPES, NEW_YORK_CHARS);
+        unterminated.extend(block(b'2', 8, &[], &NEW_YORK_TYPES, NEW_YORK_CHARS));
+        unterminated.extend(b"\nEST5EDT");
+        assert!(TzifZone::parse("Bad/Footer", &unterminated).is_err());
+    }
+}
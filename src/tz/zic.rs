@@ -0,0 +1,1176 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+/// Explicit transitions are written up to the end of this year, or of the
+/// last year a rule with an end date reaches. The footer takes over after.
+const LAST_YEAR: i64 = 2037;
+
+/// Rules starting at `min` are expanded from this year, before any zone
+/// used them.
+const FIRST_YEAR: i64 = 1800;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Weekday names, numbered from Sunday as in POSIX TZ strings.
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Which clock a time of day in the sources is read on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clock {
+    /// Local wall clock time, the default.
+    Wall,
+    /// Local standard time, suffixed `s`.
+    Standard,
+    /// UTC, suffixed `u`, `g` or `z`.
+    Universal,
+}
+
+/// The ON field of a rule and the day of an UNTIL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Day {
+    /// `5`
+    Fixed(i64),
+    /// `lastSun`
+    Last(i64),
+    /// `Sun>=8`
+    OnOrAfter(i64, i64),
+    /// `Sun<=25`
+    OnOrBefore(i64, i64),
+}
+
+impl Day {
+    /// The day it falls on in `month` of `year`, as days since 1970-01-01.
+    fn resolve(&self, year: i64, month: i64) -> i64 {
+        let first = days_from_civil(year, month, 1);
+
+        match *self {
+            Day::Fixed(day) => first + day - 1,
+            Day::Last(weekday) => {
+                let last = days_from_civil(year, month + 1, 1) - 1;
+                last - (weekday_of(last) - weekday).rem_euclid(7)
+            }
+            Day::OnOrAfter(weekday, day) => {
+                let day = first + day - 1;
+                day + (weekday - weekday_of(day)).rem_euclid(7)
+            }
+            Day::OnOrBefore(weekday, day) => {
+                let day = first + day - 1;
+                day - (weekday_of(day) - weekday).rem_euclid(7)
+            }
+        }
+    }
+}
+
+/// A `Rule` line: one change of daylight saving time, repeated every year
+/// from `from` to `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    from: i64,
+    /// `i64::MAX` for rules that are still in force.
+    to: i64,
+    month: i64,
+    day: Day,
+    at: i64,
+    clock: Clock,
+    save: i64,
+    letters: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rules {
+    None,
+    Fixed(i64),
+    Named(String),
+}
+
+/// The end of a zone line, in the local time of that line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Until {
+    year: i64,
+    month: i64,
+    day: Day,
+    at: i64,
+    clock: Clock,
+}
+
+impl Until {
+    fn to_utc(self, stdoff: i64, save: i64) -> i64 {
+        local_to_utc(
+            self.day.resolve(self.year, self.month) * 86400 + self.at,
+            self.clock,
+            stdoff,
+            save,
+        )
+    }
+}
+
+/// A `Zone` line or one of its continuation lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ZoneLine {
+    stdoff: i64,
+    rules: Rules,
+    format: String,
+    until: Option<Until>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Zone {
+    name: String,
+    lines: Vec<ZoneLine>,
+}
+
+/// A local time type as written to a TZif file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LocalType {
+    utoff: i64,
+    is_dst: bool,
+    abbreviation: String,
+}
+
+/// A zone worked out into explicit transitions and a POSIX TZ footer for the
+/// times after them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Compiled {
+    initial: LocalType,
+    transitions: Vec<(i64, LocalType)>,
+    footer: Option<String>,
+}
+
+/// The Rule, Zone and Link lines of one or more tzdata source files.
+#[derive(Debug, Clone, Default)]
+struct Sources {
+    rules: HashMap<String, Vec<Rule>>,
+    zones: Vec<Zone>,
+    /// Link names and the zones they point to.
+    links: Vec<(String, String)>,
+}
+
+/// Compiles the tzdata source files `sources`, in the input format of
+/// `zic(8)`, into one TZif file per zone and link under `output`, and returns
+/// how many were written.
+pub fn compile(sources: &[PathBuf], output: &Path) -> Result<usize> {
+    let mut parsed = Sources::default();
+
+    for path in sources {
+        parsed.parse(&fs::read_to_string(path)?, &path.display().to_string())?;
+    }
+
+    let files = parsed.tzif_files()?;
+
+    for (name, data) in &files {
+        let path = output.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)?;
+    }
+
+    Ok(files.len())
+}
+
+impl Sources {
+    fn parse(&mut self, text: &str, file: &str) -> Result<()> {
+        // The zone whose previous line had an UNTIL, so the next line
+        // continues it.
+        let mut continuing: Option<Zone> = None;
+
+        for (number, line) in text.lines().enumerate() {
+            let error = |message: String| invalid(format!("{}:{}: {}", file, number + 1, message));
+
+            let fields: Vec<&str> = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+
+            if fields.is_empty() {
+                continue;
+            }
+
+            let zone_fields = match continuing.take() {
+                Some(zone) => Some((zone, &fields[..])),
+                None => match lookup(fields[0], &["Rule", "Zone", "Link"]) {
+                    Some(0) => {
+                        let (name, rule) = parse_rule(&fields).map_err(error)?;
+                        self.rules.entry(name).or_default().push(rule);
+                        None
+                    }
+                    Some(1) if fields.len() >= 5 => {
+                        let zone = Zone {
+                            name: zone_name(fields[1]).map_err(error)?,
+                            lines: Vec::new(),
+                        };
+                        Some((zone, &fields[2..]))
+                    }
+                    Some(2) if fields.len() == 3 => {
+                        let name = zone_name(fields[2]).map_err(error)?;
+                        self.links.push((name, fields[1].to_string()));
+                        None
+                    }
+                    _ => return Err(error(format!("Unexpected line {:?}", line))),
+                },
+            };
+
+            if let Some((mut zone, fields)) = zone_fields {
+                let line = parse_zone_line(fields).map_err(error)?;
+                let ends = line.until.is_some();
+                zone.lines.push(line);
+
+                match ends {
+                    true => continuing = Some(zone),
+                    false => self.zones.push(zone),
+                }
+            }
+        }
+
+        match continuing {
+            Some(zone) => Err(invalid(format!(
+                "{}: Zone {} ends with an UNTIL but has no line after it",
+                file, zone.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The TZif data of every zone and link, by name.
+    fn tzif_files(&self) -> Result<BTreeMap<String, Vec<u8>>> {
+        let mut files = BTreeMap::new();
+
+        for zone in &self.zones {
+            let compiled = compile_zone(zone, &self.rules)
+                .map_err(|e| invalid(format!("Zone {}: {}", zone.name, e)))?;
+            files.insert(zone.name.clone(), tzif(&compiled));
+        }
+
+        // Links may point to other links, so resolve them until none are
+        // left that can be.
+        let mut pending: Vec<&(String, String)> = self.links.iter().collect();
+
+        loop {
+            let before = pending.len();
+
+            pending.retain(|(name, target)| match files.get(target).cloned() {
+                Some(data) => {
+                    files.insert(name.clone(), data);
+                    false
+                }
+                None => true,
+            });
+
+            if pending.len() == before {
+                break;
+            }
+        }
+
+        match pending.first() {
+            Some((name, target)) => Err(invalid(format!(
+                "Link {} points to unknown zone {}",
+                name, target
+            ))),
+            None => Ok(files),
+        }
+    }
+}
+
+fn compile_zone(zone: &Zone, rules: &HashMap<String, Vec<Rule>>) -> Result<Compiled> {
+    let mut initial = None;
+    let mut transitions: Vec<(i64, LocalType)> = Vec::new();
+    let mut footer = None;
+    // Where the current line starts, or `None` for the first.
+    let mut start: Option<i64> = None;
+
+    for (i, line) in zone.lines.iter().enumerate() {
+        let last = i + 1 == zone.lines.len();
+
+        let named = match &line.rules {
+            Rules::Named(name) => Some(
+                rules
+                    .get(name)
+                    .ok_or_else(|| invalid(format!("Unknown rule {}", name)))?,
+            ),
+            _ => None,
+        };
+
+        let end_year = match (line.until, named) {
+            (Some(until), _) => until.year + 1,
+            (None, Some(named)) => named
+                .iter()
+                .map(|rule| rule.to)
+                .filter(|&to| to != i64::MAX)
+                .fold(LAST_YEAR, i64::max),
+            (None, None) => LAST_YEAR,
+        };
+
+        let events = match named {
+            Some(named) => rule_events(named, line.stdoff, end_year),
+            None => Vec::new(),
+        };
+
+        // The rule in force when the line starts is the latest to have taken
+        // effect by then. Before the first, standard time uses the letters
+        // of the first rule to return to it.
+        let (mut save, mut letters) = match &line.rules {
+            Rules::None => (0, ""),
+            Rules::Fixed(save) => (*save, ""),
+            Rules::Named(_) => start
+                .and_then(|start| events.iter().rev().find(|event| event.utc <= start))
+                .map(|event| (event.save, event.letters))
+                .unwrap_or_else(|| {
+                    let letters = events.iter().find(|event| event.save == 0);
+                    (0, letters.map_or("", |event| event.letters))
+                }),
+        };
+
+        let mut push = |at: Option<i64>, save: i64, letters: &str| {
+            let local_type = LocalType {
+                utoff: line.stdoff + save,
+                is_dst: save != 0,
+                abbreviation: abbreviation(&line.format, letters, save, line.stdoff + save),
+            };
+
+            match at {
+                None => initial = Some(local_type),
+                Some(at) => {
+                    let current = transitions.last().map(|(_, t)| t).or(initial.as_ref());
+                    if current != Some(&local_type) {
+                        transitions.push((at, local_type));
+                    }
+                }
+            }
+        };
+
+        push(start, save, letters);
+
+        for event in events
+            .iter()
+            .filter(|event| start.is_none_or(|start| event.utc > start))
+        {
+            if line
+                .until
+                .is_some_and(|until| event.utc >= until.to_utc(line.stdoff, save))
+            {
+                break;
+            }
+
+            save = event.save;
+            letters = event.letters;
+            push(Some(event.utc), save, letters);
+        }
+
+        match line.until {
+            Some(until) if !last => start = Some(until.to_utc(line.stdoff, save)),
+            Some(_) => {}
+            None => footer = footer_for(line, named.map(Vec::as_slice), save, letters),
+        }
+    }
+
+    let initial = initial.ok_or_else(|| invalid("Zone has no lines".to_string()))?;
+
+    Ok(Compiled {
+        transitions: merge_transitions(&initial, transitions),
+        initial,
+        footer,
+    })
+}
+
+/// Merges each transition into the one before when it does not move the
+/// wall clock past where that one left it, as zic does when a zone line
+/// ends where one of its rules takes effect. Transitions that then change
+/// nothing are dropped.
+fn merge_transitions(
+    initial: &LocalType,
+    transitions: Vec<(i64, LocalType)>,
+) -> Vec<(i64, LocalType)> {
+    let mut merged: Vec<(i64, LocalType)> = Vec::new();
+
+    for (at, local_type) in transitions {
+        let before = |merged: &[(i64, LocalType)], n: usize| match merged.len().checked_sub(n) {
+            Some(0) | None => initial.utoff,
+            Some(i) => merged[i - 1].1.utoff,
+        };
+
+        if let Some((previous_at, previous_type)) = merged.last() {
+            if at + previous_type.utoff <= previous_at + before(&merged, 1) {
+                let last = merged.len() - 1;
+                merged[last].1 = local_type;
+                continue;
+            }
+        }
+
+        merged.push((at, local_type));
+    }
+
+    let mut current = initial.clone();
+    merged.retain(|(_, local_type)| {
+        let changed = *local_type != current;
+        current = local_type.clone();
+        changed
+    });
+
+    merged
+}
+
+/// A rule taking effect at a UTC instant.
+struct Event<'a> {
+    utc: i64,
+    save: i64,
+    letters: &'a str,
+}
+
+/// Every change made by `rules` up to `end_year`, in order. Wall clock times
+/// depend on the amount saved by the change before.
+fn rule_events(rules: &[Rule], stdoff: i64, end_year: i64) -> Vec<Event<'_>> {
+    let mut instances: Vec<(i64, &Rule)> = rules
+        .iter()
+        .flat_map(|rule| {
+            (rule.from.max(FIRST_YEAR)..=rule.to.min(end_year))
+                .map(move |year| (rule.day.resolve(year, rule.month) * 86400 + rule.at, rule))
+        })
+        .collect();
+
+    instances.sort_by_key(|&(local, rule)| local_to_utc(local, rule.clock, stdoff, 0));
+
+    let mut save = 0;
+
+    instances
+        .into_iter()
+        .map(|(local, rule)| {
+            let utc = local_to_utc(local, rule.clock, stdoff, save);
+            save = rule.save;
+            Event {
+                utc,
+                save: rule.save,
+                letters: &rule.letters,
+            }
+        })
+        .collect()
+}
+
+/// The POSIX TZ string for times after the last line's explicit transitions:
+/// its fixed offset, or the two rules that are still in force. Anything else
+/// has no footer and keeps the last local time type.
+fn footer_for(line: &ZoneLine, rules: Option<&[Rule]>, save: i64, letters: &str) -> Option<String> {
+    let fixed = |save: i64, letters: &str| {
+        let utoff = line.stdoff + save;
+        format!(
+            "{}{}",
+            posix_name(&abbreviation(&line.format, letters, save, utoff)),
+            posix_duration(-utoff)
+        )
+    };
+
+    let Some(rules) = rules else {
+        return Some(fixed(save, letters));
+    };
+
+    let ongoing: Vec<&Rule> = rules.iter().filter(|rule| rule.to == i64::MAX).collect();
+
+    let (std, dst) = match ongoing[..] {
+        [] => return Some(fixed(save, letters)),
+        [a, b] if a.save == 0 && b.save != 0 => (a, b),
+        [a, b] if a.save != 0 && b.save == 0 => (b, a),
+        _ => return None,
+    };
+
+    let dst_utoff = line.stdoff + dst.save;
+
+    // Both rules take effect at the wall clock time before them.
+    let wall = |rule: &Rule, save: i64| match rule.clock {
+        Clock::Wall => rule.at,
+        Clock::Standard => rule.at + save,
+        Clock::Universal => rule.at + line.stdoff + save,
+    };
+
+    let mut footer = format!(
+        "{}{}{}",
+        posix_name(&abbreviation(&line.format, &std.letters, 0, line.stdoff)),
+        posix_duration(-line.stdoff),
+        posix_name(&abbreviation(
+            &line.format,
+            &dst.letters,
+            dst.save,
+            dst_utoff
+        )),
+    );
+
+    if dst_utoff != line.stdoff + 3600 {
+        footer.push_str(&posix_duration(-dst_utoff));
+    }
+
+    for (rule, time) in [(dst, wall(dst, 0)), (std, wall(std, dst.save))] {
+        let (date, shift) = posix_date(rule)?;
+        footer.push(',');
+        footer.push_str(&date);
+
+        let time = time + shift * 86400;
+        if time != 7200 {
+            footer.push('/');
+            footer.push_str(&posix_duration(time));
+        }
+    }
+
+    Some(footer)
+}
+
+/// The POSIX form of the day a rule falls on, and the days to add to its
+/// time when the day is only expressible as an earlier one.
+fn posix_date(rule: &Rule) -> Option<(String, i64)> {
+    let month = rule.month;
+
+    match rule.day {
+        Day::Last(weekday) => Some((format!("M{}.5.{}", month, weekday), 0)),
+        // The first Sunday on or after the 9th is a day after the first
+        // Saturday on or after the 8th, the second Saturday.
+        Day::OnOrAfter(weekday, day) => {
+            let shift = (day - 1) % 7;
+            let week = (day - 1 - shift) / 7 + 1;
+            let weekday = (weekday - shift).rem_euclid(7);
+            (week <= 4).then(|| (format!("M{}.{}.{}", month, week, weekday), shift))
+        }
+        Day::OnOrBefore(weekday, day) if day > 6 => posix_date(&Rule {
+            day: Day::OnOrAfter(weekday, day - 6),
+            ..rule.clone()
+        }),
+        Day::Fixed(day) if !(month == 2 && day == 29) => {
+            let julian = days_from_civil(1971, month, day) - days_from_civil(1971, 1, 1) + 1;
+            Some((format!("J{}", julian), 0))
+        }
+        _ => None,
+    }
+}
+
+/// Expands a FORMAT field: `A/B` picks standard or daylight saving time,
+/// `%s` takes the rule's letters and `%z` the numeric offset.
+fn abbreviation(format: &str, letters: &str, save: i64, utoff: i64) -> String {
+    if let Some((std, dst)) = format.split_once('/') {
+        return match save {
+            0 => std.to_string(),
+            _ => dst.to_string(),
+        };
+    }
+
+    let sign = if utoff < 0 { '-' } else { '+' };
+    let (hours, minutes, seconds) = (utoff.abs() / 3600, utoff.abs() / 60 % 60, utoff.abs() % 60);
+    let numeric = match (minutes, seconds) {
+        (0, 0) => format!("{}{:02}", sign, hours),
+        (_, 0) => format!("{}{:02}{:02}", sign, hours, minutes),
+        _ => format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds),
+    };
+
+    format.replace("%s", letters).replace("%z", &numeric)
+}
+
+/// An abbreviation as POSIX TZ strings need it, quoted unless it is three or
+/// more letters.
+fn posix_name(abbreviation: &str) -> String {
+    match abbreviation.len() >= 3 && abbreviation.chars().all(|c| c.is_ascii_alphabetic()) {
+        true => abbreviation.to_string(),
+        false => format!("<{}>", abbreviation),
+    }
+}
+
+/// `[-]h[:mm[:ss]]`
+fn posix_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let (hours, minutes, rest) = (
+        seconds.abs() / 3600,
+        seconds.abs() / 60 % 60,
+        seconds.abs() % 60,
+    );
+
+    match (minutes, rest) {
+        (0, 0) => format!("{}{}", sign, hours),
+        (_, 0) => format!("{}{}:{:02}", sign, hours, minutes),
+        _ => format!("{}{}:{:02}:{:02}", sign, hours, minutes, rest),
+    }
+}
+
+fn local_to_utc(local: i64, clock: Clock, stdoff: i64, save: i64) -> i64 {
+    match clock {
+        Clock::Wall => local - stdoff - save,
+        Clock::Standard => local - stdoff,
+        Clock::Universal => local,
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar,
+/// where months past December roll over into the next year.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year + (month - 1).div_euclid(12);
+    let month = (month - 1).rem_euclid(12) + 1;
+
+    // Counting years from March puts the leap day at the end of each.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The weekday of a day since 1970-01-01, which was a Thursday, from 0 for
+/// Sunday.
+fn weekday_of(days: i64) -> i64 {
+    (days + 4).rem_euclid(7)
+}
+
+/// The index of the name that `word` abbreviates, ignoring case. An exact
+/// match wins over longer names it is also a prefix of.
+fn lookup(word: &str, names: &[&str]) -> Option<usize> {
+    let word = word.to_ascii_lowercase();
+
+    if let Some(exact) = names
+        .iter()
+        .position(|name| name.to_ascii_lowercase() == word)
+    {
+        return Some(exact);
+    }
+
+    let mut matches = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| !word.is_empty() && name.to_ascii_lowercase().starts_with(&word));
+
+    match (matches.next(), matches.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
+
+fn zone_name(name: &str) -> std::result::Result<String, String> {
+    match name.starts_with('/') || name.split('/').any(|part| part == ".." || part.is_empty()) {
+        true => Err(format!("Invalid zone name {:?}", name)),
+        false => Ok(name.to_string()),
+    }
+}
+
+fn parse_rule(fields: &[&str]) -> std::result::Result<(String, Rule), String> {
+    let [_, name, from, to, _, month, day, at, save, letters] = fields else {
+        return Err(format!("A Rule line needs 10 fields, not {}", fields.len()));
+    };
+
+    let from = match lookup(from, &["minimum"]) {
+        Some(_) => i64::MIN,
+        None => parse_year(from)?,
+    };
+    let to = match lookup(to, &["only", "maximum"]) {
+        Some(0) => from,
+        Some(_) => i64::MAX,
+        None => parse_year(to)?,
+    };
+    let (at, clock) = parse_time(at)?;
+
+    Ok((
+        name.to_string(),
+        Rule {
+            from,
+            to,
+            month: parse_month(month)?,
+            day: parse_day(day)?,
+            at,
+            clock,
+            save: parse_duration(save.trim_end_matches(['s', 'd']))?,
+            letters: match *letters {
+                "-" => String::new(),
+                letters => letters.to_string(),
+            },
+        },
+    ))
+}
+
+/// `STDOFF RULES FORMAT [UNTIL]`
+fn parse_zone_line(fields: &[&str]) -> std::result::Result<ZoneLine, String> {
+    let [stdoff, rules, format, until @ ..] = fields else {
+        return Err("A zone line needs at least 3 fields".to_string());
+    };
+
+    let rules = match *rules {
+        "-" => Rules::None,
+        rules if rules.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+            Rules::Fixed(parse_duration(rules.trim_end_matches(['s', 'd']))?)
+        }
+        name => Rules::Named(name.to_string()),
+    };
+
+    let until = match until {
+        [] => None,
+        [year, rest @ ..] if rest.len() <= 3 => {
+            let (at, clock) = rest
+                .get(2)
+                .map_or(Ok((0, Clock::Wall)), |at| parse_time(at))?;
+            Some(Until {
+                year: parse_year(year)?,
+                month: rest.first().map_or(Ok(1), |month| parse_month(month))?,
+                day: rest
+                    .get(1)
+                    .map_or(Ok(Day::Fixed(1)), |day| parse_day(day))?,
+                at,
+                clock,
+            })
+        }
+        _ => return Err(format!("Too many fields in UNTIL {:?}", until.join(" "))),
+    };
+
+    Ok(ZoneLine {
+        stdoff: parse_duration(stdoff)?,
+        rules,
+        format: format.to_string(),
+        until,
+    })
+}
+
+fn parse_year(field: &str) -> std::result::Result<i64, String> {
+    field
+        .parse()
+        .map_err(|_| format!("Invalid year {:?}", field))
+}
+
+fn parse_month(field: &str) -> std::result::Result<i64, String> {
+    lookup(field, &MONTHS)
+        .map(|month| month as i64 + 1)
+        .ok_or_else(|| format!("Invalid month {:?}", field))
+}
+
+fn parse_weekday(field: &str) -> std::result::Result<i64, String> {
+    lookup(field, &WEEKDAYS)
+        .map(|weekday| weekday as i64)
+        .ok_or_else(|| format!("Invalid weekday {:?}", field))
+}
+
+fn parse_day(field: &str) -> std::result::Result<Day, String> {
+    let day = |day: &str| -> std::result::Result<i64, String> {
+        match day.parse() {
+            Ok(day @ 1..=31) => Ok(day),
+            _ => Err(format!("Invalid day {:?}", field)),
+        }
+    };
+
+    if let Some(weekday) = field.strip_prefix("last") {
+        return Ok(Day::Last(parse_weekday(weekday)?));
+    }
+    if let Some((weekday, after)) = field.split_once(">=") {
+        return Ok(Day::OnOrAfter(parse_weekday(weekday)?, day(after)?));
+    }
+    if let Some((weekday, before)) = field.split_once("<=") {
+        return Ok(Day::OnOrBefore(parse_weekday(weekday)?, day(before)?));
+    }
+
+    Ok(Day::Fixed(day(field)?))
+}
+
+/// A time of day and the clock it is on, from its suffix.
+fn parse_time(field: &str) -> std::result::Result<(i64, Clock), String> {
+    let (time, clock) = match field.char_indices().last() {
+        Some((i, 'w')) => (&field[..i], Clock::Wall),
+        Some((i, 's')) => (&field[..i], Clock::Standard),
+        Some((i, 'u' | 'g' | 'z')) => (&field[..i], Clock::Universal),
+        _ => (field, Clock::Wall),
+    };
+
+    Ok((parse_duration(time)?, clock))
+}
+
+/// `[-]h[:mm[:ss[.fraction]]]` in seconds, where `-` alone is zero. Fractions
+/// of a second are dropped.
+fn parse_duration(field: &str) -> std::result::Result<i64, String> {
+    if field == "-" {
+        return Ok(0);
+    }
+
+    let error = || format!("Invalid time {:?}", field);
+    let (sign, rest) = match field.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, field),
+    };
+
+    let mut seconds = 0;
+    let mut parts = rest.split(':');
+
+    for unit in [3600, 60, 1] {
+        let Some(part) = parts.next() else {
+            break;
+        };
+        let whole = part.split('.').next().unwrap_or_default();
+        seconds += unit * whole.parse::<i64>().map_err(|_| error())?;
+    }
+
+    match parts.next() {
+        Some(_) => Err(error()),
+        None => Ok(sign * seconds),
+    }
+}
+
+/// A TZif file of `compiled`: a version 1 block for the transitions that fit
+/// in 32 bits, a version 2 block for all of them, then the footer. Version 3
+/// is declared when the footer needs its extensions.
+fn tzif(compiled: &Compiled) -> Vec<u8> {
+    let mut types = vec![&compiled.initial];
+    let mut indices = Vec::new();
+
+    for (_, local_type) in &compiled.transitions {
+        let index = match types.iter().position(|t| *t == local_type) {
+            Some(index) => index,
+            None => {
+                types.push(local_type);
+                types.len() - 1
+            }
+        };
+        indices.push(index as u8);
+    }
+
+    let mut chars: Vec<u8> = Vec::new();
+    let mut infos = Vec::new();
+
+    for local_type in &types {
+        let mut abbreviation = local_type.abbreviation.as_bytes().to_vec();
+        abbreviation.push(0);
+
+        let index = match chars
+            .windows(abbreviation.len())
+            .position(|window| window == abbreviation)
+        {
+            Some(index) => index,
+            None => {
+                chars.extend(&abbreviation);
+                chars.len() - abbreviation.len()
+            }
+        };
+
+        infos.push((local_type.utoff as i32, local_type.is_dst, index as u8));
+    }
+
+    let footer = compiled.footer.as_deref().unwrap_or_default();
+    let version = match footer.split(',').skip(1).any(|rule| {
+        rule.split_once('/')
+            .and_then(|(_, time)| parse_duration(time).ok())
+            .is_some_and(|time| !(0..=24 * 3600).contains(&time))
+    }) {
+        true => b'3',
+        false => b'2',
+    };
+
+    let small: Vec<(i64, u8)> = compiled
+        .transitions
+        .iter()
+        .zip(&indices)
+        .filter(|((at, _), _)| i32::try_from(*at).is_ok())
+        .map(|((at, _), index)| (*at, *index))
+        .collect();
+    let all: Vec<(i64, u8)> = compiled
+        .transitions
+        .iter()
+        .zip(&indices)
+        .map(|((at, _), index)| (*at, *index))
+        .collect();
+
+    let mut data = Vec::new();
+
+    for (transitions, time_size) in [(small, 4), (all, 8)] {
+        data.extend(b"TZif");
+        data.push(version);
+        data.extend([0; 15]);
+
+        for count in [0, 0, 0, transitions.len(), infos.len(), chars.len()] {
+            data.extend((count as u32).to_be_bytes());
+        }
+
+        for (at, _) in &transitions {
+            match time_size {
+                4 => data.extend((*at as i32).to_be_bytes()),
+                _ => data.extend(at.to_be_bytes()),
+            }
+        }
+        data.extend(transitions.iter().map(|(_, index)| index));
+
+        for (utoff, is_dst, index) in &infos {
+            data.extend(utoff.to_be_bytes());
+            data.push(*is_dst as u8);
+            data.push(*index);
+        }
+        data.extend(&chars);
+    }
+
+    data.push(b'\n');
+    data.extend(footer.as_bytes());
+    data.push(b'\n');
+
+    data
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tz::tzif::TzifZone;
+
+    use super::*;
+
+    fn compile_source(text: &str) -> BTreeMap<String, Vec<u8>> {
+        let mut sources = Sources::default();
+        sources.parse(text, "test").unwrap();
+        sources.tzif_files().unwrap()
+    }
+
+    fn zone(text: &str, name: &str) -> Compiled {
+        let mut sources = Sources::default();
+        sources.parse(text, "test").unwrap();
+        let zone = sources.zones.iter().find(|zone| zone.name == name).unwrap();
+        compile_zone(zone, &sources.rules).unwrap()
+    }
+
+    fn utc(year: i64, month: i64, day: i64, time: &str) -> i64 {
+        days_from_civil(year, month, day) * 86400 + parse_duration(time).unwrap()
+    }
+
+    const BERLIN: &str = "
+        # Rules and zones may be abbreviated as in tzdata.zi.
+        Rule EU 1977 1980 - Apr Sun>=1 1:00u 1:00 S
+        Rule EU 1977 only - Sep lastSun 1:00u 0 -
+        Rule EU 1978 only - Oct 1 1:00u 0 -
+        Rule EU 1979 1995 - Sep lastSun 1:00u 0 -
+        R EU 1981 ma - Mar lastSu 1u 1 S
+        R EU 1996 ma - O lastSu 1u 0 -
+        Zone Europe/Berlin 0:53:28 - LMT 1893 Apr
+            1:00 - CET 1980
+            1:00 EU CE%sT
+        Link Europe/Berlin Europe/Busingen
+        L Europe/Busingen Europe/Zurich_Link
+    ";
+
+    #[test]
+    fn civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2023, 13, 1), days_from_civil(2024, 1, 1));
+        assert_eq!(weekday_of(0), 4);
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(parse_duration("2"), Ok(7200));
+        assert_eq!(parse_duration("-0:30"), Ok(-1800));
+        assert_eq!(parse_duration("0:53:28.5"), Ok(3208));
+        assert_eq!(parse_duration("-"), Ok(0));
+        assert!(parse_duration("1:2:3:4").is_err());
+
+        assert_eq!(parse_time("1:00u"), Ok((3600, Clock::Universal)));
+        assert_eq!(parse_time("2:00s"), Ok((7200, Clock::Standard)));
+        assert_eq!(parse_time("24:00"), Ok((86400, Clock::Wall)));
+
+        assert_eq!(parse_day("lastSun"), Ok(Day::Last(0)));
+        assert_eq!(parse_day("Sa>=8"), Ok(Day::OnOrAfter(6, 8)));
+        assert_eq!(parse_day("Fri<=1"), Ok(Day::OnOrBefore(5, 1)));
+        assert_eq!(parse_day("31"), Ok(Day::Fixed(31)));
+        assert!(parse_day("32").is_err());
+
+        assert_eq!(parse_month("Ja"), Ok(1));
+        assert!(parse_month("Ju").is_err());
+        assert_eq!(lookup("ma", &["only", "maximum"]), Some(1));
+    }
+
+    #[test]
+    fn days_of_rules() {
+        // Last Sunday of March 2024, first Sunday on or after 8 March 2024
+        // and the Friday on or before 1 April 2022.
+        assert_eq!(Day::Last(0).resolve(2024, 3), days_from_civil(2024, 3, 31));
+        assert_eq!(
+            Day::OnOrAfter(0, 8).resolve(2024, 3),
+            days_from_civil(2024, 3, 10)
+        );
+        assert_eq!(
+            Day::OnOrBefore(5, 1).resolve(2022, 4),
+            days_from_civil(2022, 4, 1)
+        );
+    }
+
+    #[test]
+    fn zone_lines_and_rules() {
+        let berlin = zone(BERLIN, "Europe/Berlin");
+
+        assert_eq!(berlin.initial.abbreviation, "LMT");
+        assert_eq!(berlin.initial.utoff, 3208);
+
+        let (at, cet) = &berlin.transitions[0];
+        assert_eq!(*at, utc(1893, 3, 31, "23:06:32"));
+        assert_eq!(
+            (cet.utoff, cet.is_dst, cet.abbreviation.as_str()),
+            (3600, false, "CET")
+        );
+
+        let (at, cest) = &berlin.transitions[1];
+        assert_eq!(*at, utc(1980, 4, 6, "1:00"));
+        assert_eq!(
+            (cest.utoff, cest.is_dst, cest.abbreviation.as_str()),
+            (7200, true, "CEST")
+        );
+
+        assert!(berlin
+            .transitions
+            .iter()
+            .any(|(at, local_type)| *at == utc(2037, 10, 25, "1:00") && !local_type.is_dst));
+        assert!(berlin.transitions.last().unwrap().0 < utc(2038, 1, 1, "0"));
+        assert_eq!(berlin.footer.as_deref(), Some("CET-1CEST,M3.5.0,M10.5.0/3"));
+    }
+
+    #[test]
+    fn footers() {
+        let santiago = "
+            Rule Chile 2023 max - Sep Sun>=2 4:00u 1:00 -
+            Rule Chile 2024 max - Apr Sun>=2 3:00u 0 -
+            Zone America/Santiago -4:00 Chile %z
+        ";
+        assert_eq!(
+            zone(santiago, "America/Santiago").footer.as_deref(),
+            Some("<-04>4<-03>,M9.1.6/24,M4.1.6/24")
+        );
+
+        // Negative daylight saving time keeps the letters of the rules.
+        let dublin = "
+            Rule Eire 1981 max - Mar lastSun 1:00u 0 -
+            Rule Eire 1996 max - Oct lastSun 1:00u -1:00 -
+            Zone Europe/Dublin 1:00 Eire IST/GMT
+        ";
+        assert_eq!(
+            zone(dublin, "Europe/Dublin").footer.as_deref(),
+            Some("IST-1GMT0,M10.5.0,M3.5.0/1")
+        );
+
+        let kolkata = "Zone Asia/Kolkata 5:30 - IST";
+        assert_eq!(
+            zone(kolkata, "Asia/Kolkata").footer.as_deref(),
+            Some("IST-5:30")
+        );
+
+        // Once every rule has ended, the last of them stays in force.
+        let sao_paulo = "
+            Rule Brazil 2018 only - Nov Sun>=1 0:00 1:00 -
+            Rule Brazil 2019 only - Feb Sun>=15 0:00 0 -
+            Zone America/Sao_Paulo -3:00 Brazil %z
+        ";
+        assert_eq!(
+            zone(sao_paulo, "America/Sao_Paulo").footer.as_deref(),
+            Some("<-03>3")
+        );
+    }
+
+    #[test]
+    fn transitions_where_lines_meet() {
+        // Daylight saving time starts at the very moment the offset changes,
+        // so the two become one transition, as zic makes them.
+        let buenos_aires = "
+            Rule Arg 1999 only - Oct Sun>=1 0:00 1:00 -
+            Rule Arg 2000 only - Mar 3 0:00 0 -
+            Zone America/Argentina/Buenos_Aires -3:00 - %z 1999 Oct 3
+                -4:00 Arg %z 2000 Mar 3
+                -3:00 - %z
+        ";
+        let compiled = zone(buenos_aires, "America/Argentina/Buenos_Aires");
+
+        let transitions: Vec<(i64, i64, bool)> = compiled
+            .transitions
+            .iter()
+            .map(|(at, local_type)| (*at, local_type.utoff, local_type.is_dst))
+            .collect();
+
+        assert_eq!(
+            transitions,
+            [
+                (utc(1999, 10, 3, "3:00"), -10800, true),
+                (utc(2000, 3, 3, "3:00"), -10800, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn tzif_files_read_back() {
+        let files = compile_source(BERLIN);
+
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            ["Europe/Berlin", "Europe/Busingen", "Europe/Zurich_Link"]
+        );
+        assert_eq!(files["Europe/Berlin"], files["Europe/Zurich_Link"]);
+
+        let berlin = TzifZone::parse("Europe/Berlin", &files["Europe/Berlin"]).unwrap();
+
+        assert_eq!(berlin.initial_type().abbreviation, "LMT");
+        assert_eq!(
+            berlin.footer().map(|footer| footer.source.as_str()),
+            Some("CET-1CEST,M3.5.0,M10.5.0/3")
+        );
+        assert_eq!(berlin.type_at_utc(utc(1975, 7, 1, "0")).abbreviation, "CET");
+        assert_eq!(
+            berlin.type_at_utc(utc(2020, 7, 1, "0")).abbreviation,
+            "CEST"
+        );
+        assert_eq!(
+            berlin.type_at_utc(utc(2050, 7, 1, "0")).abbreviation,
+            "CEST"
+        );
+        assert_eq!(
+            berlin.type_at_utc(utc(2050, 12, 1, "0")).abbreviation,
+            "CET"
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let mut sources = Sources::default();
+        let error = sources
+            .parse("Rule EU 1981 max - Mar lastSun\n", "europe")
+            .unwrap_err();
+        assert!(error.to_string().starts_with("europe:1: "));
+
+        let mut sources = Sources::default();
+        assert!(sources
+            .parse("Zone Europe/Berlin 1:00 - CET 1980\n", "europe")
+            .is_err());
+
+        let mut sources = Sources::default();
+        sources
+            .parse("Zone Europe/Berlin 1:00 EU CE%sT\n", "europe")
+            .unwrap();
+        assert!(sources.tzif_files().is_err());
+
+        let mut sources = Sources::default();
+        sources
+            .parse("Link Europe/Nowhere Europe/Somewhere\n", "backward")
+            .unwrap();
+        assert!(sources.tzif_files().is_err());
+
+        let mut sources = Sources::default();
+        assert!(sources
+            .parse("Zone ../escape 1:00 - CET\n", "europe")
+            .is_err());
+    }
+}
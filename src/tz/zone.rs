@@ -0,0 +1,175 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+use chrono::{FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::{Tz, TzOffset};
+
+use super::posix::{PosixOffset, PosixZone};
+
+/// A time zone given either as an IANA name such as `Europe/Berlin` or as a
+/// raw POSIX TZ string such as `CET-1CEST,M3.5.0,M10.5.0/3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Zone {
+    Named(Tz),
+    Posix(PosixZone),
+}
+
+impl FromStr for Zone {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        if let Ok(tz) = source.parse::<Tz>() {
+            return Ok(Zone::Named(tz));
+        }
+
+        source.parse().map(Zone::Posix).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown time zone {:?}", source),
+            )
+        })
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Named(tz) => f.write_str(tz.name()),
+            Zone::Posix(zone) => fmt::Display::fmt(&zone.0, f),
+        }
+    }
+}
+
+/// The offset of a [`Zone`] at some instant.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ZoneOffset {
+    Named(TzOffset),
+    Posix(PosixOffset),
+}
+
+impl Offset for ZoneOffset {
+    fn fix(&self) -> FixedOffset {
+        match self {
+            ZoneOffset::Named(offset) => offset.fix(),
+            ZoneOffset::Posix(offset) => offset.fix(),
+        }
+    }
+}
+
+impl fmt::Display for ZoneOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZoneOffset::Named(offset) => fmt::Display::fmt(offset, f),
+            ZoneOffset::Posix(offset) => fmt::Display::fmt(offset, f),
+        }
+    }
+}
+
+impl fmt::Debug for ZoneOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl TimeZone for Zone {
+    type Offset = ZoneOffset;
+
+    fn from_offset(offset: &ZoneOffset) -> Self {
+        match offset {
+            ZoneOffset::Named(offset) => Zone::Named(Tz::from_offset(offset)),
+            ZoneOffset::Posix(offset) => Zone::Posix(PosixZone::from_offset(offset)),
+        }
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<ZoneOffset> {
+        match self {
+            Zone::Named(tz) => tz.offset_from_local_date(local).map(ZoneOffset::Named),
+            Zone::Posix(zone) => zone.offset_from_local_date(local).map(ZoneOffset::Posix),
+        }
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<ZoneOffset> {
+        match self {
+            Zone::Named(tz) => tz.offset_from_local_datetime(local).map(ZoneOffset::Named),
+            Zone::Posix(zone) => zone
+                .offset_from_local_datetime(local)
+                .map(ZoneOffset::Posix),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> ZoneOffset {
+        match self {
+            Zone::Named(tz) => ZoneOffset::Named(tz.offset_from_utc_date(utc)),
+            Zone::Posix(zone) => ZoneOffset::Posix(zone.offset_from_utc_date(utc)),
+        }
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> ZoneOffset {
+        match self {
+            Zone::Named(tz) => ZoneOffset::Named(tz.offset_from_utc_datetime(utc)),
+            Zone::Posix(zone) => ZoneOffset::Posix(zone.offset_from_utc_datetime(utc)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn local(month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn names_and_posix_strings() {
+        let named: Zone = "Europe/Berlin".parse().unwrap();
+        let posix: Zone = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+
+        assert!(matches!(named, Zone::Named(_)));
+        assert!(matches!(posix, Zone::Posix(_)));
+        assert_eq!(named.to_string(), "Europe/Berlin");
+        assert_eq!(posix.to_string(), "CET-1CEST,M3.5.0,M10.5.0/3");
+        assert!("Europe/Nowhere".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn posix_strings_agree_with_names() {
+        let named: Zone = "Europe/Berlin".parse().unwrap();
+        let posix: Zone = "CET-1CEST,M3.5.0,M10.5.0/3".parse().unwrap();
+
+        for (month, day, hour) in [(1, 15, 12), (3, 31, 3), (7, 1, 0), (10, 27, 4)] {
+            let utc = local(month, day, hour);
+            let offset = |zone: &Zone| zone.offset_from_utc_datetime(&utc);
+
+            assert_eq!(offset(&named).fix(), offset(&posix).fix());
+            assert_eq!(offset(&named).to_string(), offset(&posix).to_string());
+        }
+    }
+
+    #[test]
+    fn gaps_and_overlaps() {
+        for source in ["Europe/Berlin", "CET-1CEST,M3.5.0,M10.5.0/3"] {
+            let zone: Zone = source.parse().unwrap();
+
+            assert!(matches!(
+                zone.from_local_datetime(&local(3, 31, 2)),
+                LocalResult::None
+            ));
+            assert!(matches!(
+                zone.from_local_datetime(&local(10, 27, 2)),
+                LocalResult::Ambiguous(_, _)
+            ));
+
+            let summer = zone.from_local_datetime(&local(7, 1, 12)).unwrap();
+            assert_eq!(summer.offset().fix().local_minus_utc(), 7200);
+        }
+    }
+}